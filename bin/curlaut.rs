@@ -4,6 +4,7 @@ use curlaut::cli::clap_config::Cli;
 use curlaut::cli::clap_config::MainCommand::{
     Config, DeleteRequest, GetRequest, PostRequest, PutRequest,
 };
+use curlaut::cli::exit_code::CurlautExitCode;
 use curlaut::cli::request_executor as req;
 use curlaut::output::stdio::CurlautStdOutput;
 use curlaut::request::request_spec::HttpRequestMethod::{DELETE, GET, POST, PUT};
@@ -13,6 +14,13 @@ use std::process::exit;
 fn main() {
     let mut io = CurlautStdOutput::new();
     let cli = Cli::parse();
+    let verbose = cli.verbose
+        || match &cli.command {
+            GetRequest(args) | PostRequest(args) | PutRequest(args) | DeleteRequest(args) => {
+                args.verbose
+            }
+            Config { .. } => false,
+        };
     let result = match cli.command {
         Config { command } => auth::execute_command(command, &mut io),
         GetRequest(args) => req::execute_request(GET, args, &mut io),
@@ -22,11 +30,16 @@ fn main() {
     };
     match result {
         Ok(_) => {
-            exit(0);
+            exit(CurlautExitCode::Success.code());
         }
         Err(err) => {
-            writeln!(std::io::stderr(), "{err:?}").expect("Failed to write to stderr");
-            exit(1);
+            // the full chain of causes is only for verbose mode
+            if verbose {
+                writeln!(std::io::stderr(), "{err:?}").expect("Failed to write to stderr");
+            } else {
+                writeln!(std::io::stderr(), "{err}").expect("Failed to write to stderr");
+            }
+            exit(CurlautExitCode::from_error(&err).code());
         }
     }
 }
@@ -0,0 +1,45 @@
+use crate::request::request_error::RequestError;
+use url::Url;
+
+/// Failure to get a token from Keycloak.
+#[derive(Debug, thiserror::Error)]
+pub enum AuthError {
+    #[error("Keycloak '{alias}' rejected credentials: {description}")]
+    InvalidGrant { alias: String, description: String },
+    #[error("Keycloak '{alias}' rejected token request with status {status}: {error}")]
+    Rejected {
+        alias: String,
+        status: u16,
+        error: String,
+    },
+    #[error("Keycloak '{alias}' is unreachable at {url}")]
+    Unreachable {
+        alias: String,
+        url: Url,
+        #[source]
+        source: reqwest::Error,
+    },
+    #[error("Keycloak '{alias}' didn't respond in time at {url}")]
+    Timeout {
+        alias: String,
+        url: Url,
+        #[source]
+        source: reqwest::Error,
+    },
+    #[error("Keycloak '{alias}' returned invalid token response: {reason}")]
+    BadResponse { alias: String, reason: String },
+    #[error("Failed to build http client for Keycloak '{alias}'")]
+    Client {
+        alias: String,
+        #[source]
+        source: Box<RequestError>,
+    },
+    #[error("Failed to build token URL of Keycloak '{alias}'")]
+    InvalidTokenUrl {
+        alias: String,
+        #[source]
+        source: url::ParseError,
+    },
+    #[error("Failed to write output")]
+    Output(#[from] std::io::Error),
+}
@@ -1,17 +1,27 @@
-use crate::auth::HttpAuthorization;
+use crate::auth::auth_error::AuthError;
+use crate::auth::auth_error::AuthError::{
+    BadResponse, Client, InvalidGrant, InvalidTokenUrl, Rejected, Timeout, Unreachable,
+};
+use crate::auth::{HttpAuthorization, TokenSource};
 use crate::keycloak::keycloak_config::KeycloakConfig;
+use crate::output::har::{HarEntry, HarRecorder, HarRequest, HarResponse};
 use crate::output::CurlautOutput;
-use anyhow::{bail, Context};
+use crate::request::proxy::{without_credentials, ProxyOptions, ProxyRules};
+use crate::request::request_error::RequestError;
+use crate::request::retry::{self, RetryPolicy};
+use crate::request::timings::HttpTimings;
+use crate::request::tls::{self, TlsOptions, TlsRecorder};
 use serde_json::Value;
 use std::collections::HashMap;
 use std::fmt::Display;
 use std::io::Write;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 use url::Url;
 
 #[derive(Debug)]
 pub struct JwtToken {
     pub token_value: String,
+    pub source: TokenSource,
 }
 
 impl HttpAuthorization for JwtToken {
@@ -26,11 +36,29 @@ impl Display for JwtToken {
     }
 }
 
-pub fn get_jwt(config: &KeycloakConfig, io: &mut impl CurlautOutput) -> anyhow::Result<JwtToken> {
-    let http_client = reqwest::blocking::Client::new();
+/// `tls`, `proxy` and `retry` are the options of the target request,
+/// Keycloak is often behind the same private CA and network. `timeout` limits the whole exchange.
+pub fn get_jwt(
+    config: &KeycloakConfig,
+    tls: &TlsOptions,
+    proxy: &ProxyOptions,
+    retry: &RetryPolicy,
+    timeout: Duration,
+    har: Option<&mut HarRecorder>,
+    io: &mut impl CurlautOutput,
+) -> Result<JwtToken, AuthError> {
     let keycloak_url = &config.url;
-    let token_url = build_token_url(keycloak_url, &config.realm)
-        .with_context(|| format!("Failed to build auth token url to {}", config.alias))?;
+    let token_url =
+        build_token_url(keycloak_url, &config.realm).map_err(|source| InvalidTokenUrl {
+            alias: config.alias.clone(),
+            source,
+        })?;
+    let client_error = |source| Client {
+        alias: config.alias.clone(),
+        source: Box::new(source),
+    };
+    let proxy_rules = proxy.rules().map_err(client_error)?;
+    let http_client = build_http_client(tls, &proxy_rules).map_err(client_error)?;
     let mut params = HashMap::new();
     params.insert("grant_type", "password");
     params.insert("client_id", &config.client_id);
@@ -39,31 +67,119 @@ pub fn get_jwt(config: &KeycloakConfig, io: &mut impl CurlautOutput) -> anyhow::
     params.insert("password", &config.password);
     params.insert("scope", "openid profile email");
     writeln!(io.verbose(), "Requesting JWT token using POST {token_url}")?;
+    if let Some(proxy) = proxy_rules.proxy_for(&token_url) {
+        writeln!(io.verbose(), "* Via proxy {}", without_credentials(proxy))?;
+    }
+    let unreachable = |source: reqwest::Error| {
+        let alias = config.alias.clone();
+        let url = token_url.clone();
+        if source.is_timeout() {
+            Timeout { alias, url, source }
+        } else {
+            Unreachable { alias, url, source }
+        }
+    };
     let get_jwt_request = http_client
-        .post(token_url)
+        .post(token_url.clone())
         .form(&params)
-        .timeout(Duration::from_secs(60))
+        .timeout(timeout)
         .build()
-        .with_context(|| "Failed to build auth request")?;
-    let jwt_result = http_client
-        .execute(get_jwt_request)
-        .with_context(|| "Failed to execute auth request")?;
-    if !jwt_result.status().is_success() {
-        bail!(
-            "Auth request failed with status: {}",
-            jwt_result.status()
-        );
+        .map_err(unreachable)?;
+    let started = Instant::now();
+    let started_at = SystemTime::now();
+    let har_request = har.is_some().then(|| HarRequest::new(&get_jwt_request));
+    // password grant doesn't change anything on the server, so it's safe to repeat
+    let jwt_result = retry::execute_with_retry(&http_client, get_jwt_request, retry, true, io)?
+        .map_err(unreachable)?;
+    let start_transfer = started.elapsed();
+    let status = jwt_result.status();
+    let version = jwt_result.version();
+    let headers = jwt_result.headers().clone();
+    let body = jwt_result.bytes().map_err(unreachable)?;
+    if let (Some(recorder), Some(request)) = (har, har_request) {
+        let timings = HttpTimings {
+            start_transfer: Some(start_transfer),
+            total: started.elapsed(),
+            ..HttpTimings::default()
+        };
+        // reqwest decodes nothing, the body is as received
+        let size = body.len() as u64;
+        let response = HarResponse::new(status, version, &headers, Some(&body), size, size);
+        recorder.record(HarEntry::new(started_at, request, response, &timings))?;
+    }
+    let bad_response = |reason: &str| BadResponse {
+        alias: config.alias.clone(),
+        reason: reason.to_owned(),
+    };
+    let response = serde_json::from_slice::<HashMap<String, Value>>(&body)
+        .map_err(|_| bad_response("not a JSON object"));
+    if !status.is_success() {
+        return Err(to_rejected_error(config, status.as_u16(), response.ok()));
     }
-    let response: HashMap<String, Value> = jwt_result
-        .json()
-        .with_context(|| "Failed to parse auth response as JSON")?;
+    let response = response?;
     let access_token = response
         .get("access_token")
-        .with_context(|| "Missing access token")?;
-    let access_token_value = access_token.as_str()
-        .with_context(|| "Invalid access token value: must be a string")?;
+        .ok_or_else(|| bad_response("missing access token"))?;
+    let access_token_value = access_token
+        .as_str()
+        .ok_or_else(|| bad_response("access token must be a string"))?;
     let token_value = access_token_value.to_owned();
-    Ok(JwtToken { token_value })
+    Ok(JwtToken {
+        token_value,
+        source: TokenSource::PasswordGrant,
+    })
+}
+
+fn build_http_client(
+    tls: &TlsOptions,
+    proxy_rules: &ProxyRules,
+) -> Result<reqwest::blocking::Client, RequestError> {
+    // pins are of the target servers, not of Keycloak, and `--insecure` is for them only,
+    // the password and client secret are never sent over unverified TLS
+    let tls = TlsOptions {
+        insecure: false,
+        pinned_public_keys: Vec::new(),
+        ..tls.clone()
+    };
+    let tls_config = tls::client_config(&tls, false, &TlsRecorder::default())?;
+    let client_builder =
+        reqwest::blocking::ClientBuilder::new().use_preconfigured_tls(tls_config);
+    proxy_rules
+        .apply(client_builder)?
+        .build()
+        .map_err(RequestError::Client)
+}
+
+/// Keycloak reports OAuth errors like `{"error":"invalid_grant","error_description":"..."}`
+fn to_rejected_error(
+    config: &KeycloakConfig,
+    status: u16,
+    response: Option<HashMap<String, Value>>,
+) -> AuthError {
+    let field = |name: &str| {
+        response
+            .as_ref()
+            .and_then(|response| response.get(name))
+            .and_then(|value| value.as_str())
+            .map(|value| value.to_owned())
+    };
+    let error = field("error");
+    let description = field("error_description");
+    match error.as_deref() {
+        Some("invalid_grant") => InvalidGrant {
+            alias: config.alias.clone(),
+            description: description.unwrap_or_else(|| "invalid_grant".to_owned()),
+        },
+        _ => Rejected {
+            alias: config.alias.clone(),
+            status,
+            error: match (error, description) {
+                (Some(error), Some(description)) => format!("{error} ({description})"),
+                (Some(error), None) => error,
+                (None, _) => "no OAuth error in response".to_owned(),
+            },
+        },
+    }
 }
 
 fn build_token_url(keycloak_url: &Url, realm: &str) -> Result<Url, url::ParseError> {
@@ -1,7 +1,31 @@
-use std::fmt::Display;
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
 
+pub mod auth_error;
 pub mod authenticator;
 
 pub trait HttpAuthorization: Display {
     fn get_authorization_value(&self) -> String;
 }
+
+/// How the token sent with the request was obtained.
+#[derive(Debug, Clone)]
+pub struct AuthInfo {
+    pub alias: String,
+    pub token_source: TokenSource,
+    pub time: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenSource {
+    /// requested from Keycloak with username and password
+    PasswordGrant,
+}
+
+impl Display for TokenSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenSource::PasswordGrant => write!(f, "password_grant"),
+        }
+    }
+}
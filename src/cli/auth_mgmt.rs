@@ -1,10 +1,9 @@
 use crate::cli::auth_config_file_path;
 use crate::cli::clap_config::KeycloakCommand;
 use crate::cli::clap_config::KeycloakCommand::SetDefault;
-use crate::keycloak::keycloak_config::KeycloakConfig;
+use crate::keycloak::keycloak_config::{KeycloakConfig, KeycloakTls};
 use crate::keycloak::keycloak_registry::KeycloakRegistry;
 use crate::output::CurlautOutput;
-use anyhow::Context;
 use std::io::Write;
 use KeycloakCommand::{Add, List, Remove};
 
@@ -25,6 +24,17 @@ pub fn execute_command(
             username,
             password,
             default,
+            base_url,
+            default_headers,
+            routes,
+            cacert,
+            capath,
+            cert,
+            key,
+            pinned_public_keys,
+            proxy,
+            noproxy,
+            persist_cookies,
         } => {
             writeln!(io.common(), "Adding keycloak with alias: {alias}")?;
             let result = KeycloakConfig::new(
@@ -36,8 +46,20 @@ pub fn execute_command(
                 username,
                 password,
                 *default,
-            )
-                .with_context(|| "Failed to create keycloak config")?;
+                base_url.as_deref(),
+                default_headers,
+                routes,
+                proxy.as_deref(),
+                noproxy.as_deref(),
+                *persist_cookies,
+                KeycloakTls {
+                    ca_cert: cacert.clone(),
+                    ca_path: capath.clone(),
+                    cert: cert.clone(),
+                    key: key.clone(),
+                    pinned_public_keys: pinned_public_keys.clone(),
+                },
+            )?;
             keycloak_registry.add_keycloak(result)?;
             keycloak_registry.save_to_file(config_file_path)?;
             Ok(())
@@ -54,7 +76,7 @@ pub fn execute_command(
             keycloak_registry.save_to_file(config_file_path)?;
             Ok(())
         }
-        List {} => {
+        List => {
             for kc in keycloak_registry.get_all() {
                 writeln!(io.common(), "{kc}")?;
             }
@@ -1,4 +1,10 @@
+use crate::output::envelope::OutputFormat;
+use crate::output::pretty::PrettyMode;
+use crate::request::request_spec::ContinueAt;
+use crate::request::routing::{ConnectTo, ResolveOverride};
 use clap::Args;
+use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(clap::Parser)]
 #[command(about = "Curl with OAuth via Keycloak", long_about = None)]
@@ -30,29 +36,316 @@ pub enum MainCommand {
 
 #[derive(Args, Debug)]
 pub struct HttpRequestArgs {
+    #[arg(help = "Absolute URL or path relative to base URL of the Keycloak config")]
     pub url: String,
+    #[arg(
+        value_name = "REQUEST_ITEM",
+        help = "For example: 'name=value', 'count:=1', 'a[b]=c', 'q==search', 'Accept:text/plain', 'text@file.txt'"
+    )]
+    pub items: Vec<String>,
+    #[arg(
+        short,
+        long,
+        value_name = "alias",
+        help = "Keycloak config to use, chosen by routes or default if not set"
+    )]
+    pub auth: Option<String>,
     #[arg(
         name = "Header",
         short,
         long = "header",
-        help = "For example: 'Content-Type: application/json'"
+        help = "For example: 'Content-Type: application/json', 'Name:' removes header, 'Name;' sends empty value"
     )]
     pub headers: Vec<String>,
+    #[arg(
+        value_name = "name=value",
+        short,
+        long,
+        help = "Query parameter, will be URL encoded"
+    )]
+    pub query: Vec<String>,
+    #[arg(
+        short = 'G',
+        long,
+        conflicts_with_all = ["json_body", "json_body_file"],
+        help = "Send body fields as query parameters"
+    )]
+    pub get: bool,
     #[arg(value_name = "json string", long, help = "JSON Body")]
     pub json_body: Option<String>,
     #[arg(value_name = "path to json file", long, help = "Path to file to be JSON Body")]
     pub json_body_file: Option<String>,
     #[arg(short, long)]
     pub verbose: bool,
+    #[arg(
+        short,
+        long,
+        conflicts_with = "fail_with_body",
+        help = "Fail with exit code 6 on 4xx and 7 on 5xx status without printing body"
+    )]
+    pub fail: bool,
+    #[arg(long, help = "Like --fail, but print the response body")]
+    pub fail_with_body: bool,
+    #[arg(
+        long,
+        value_enum,
+        help = "Format and colorize JSON body and headers, by default only for terminal"
+    )]
+    pub pretty: Option<PrettyMode>,
+    #[arg(
+        long,
+        value_name = "expr",
+        help = "Print part of JSON response, for example: '.items[].id', '.items | length'"
+    )]
+    pub select: Option<String>,
+    #[arg(short, long, requires = "select", help = "Print selected strings without quotes")]
+    pub raw_output: bool,
+    #[arg(
+        short,
+        long,
+        value_name = "file",
+        conflicts_with = "select",
+        help = "Save response body to file"
+    )]
+    pub output: Option<PathBuf>,
+    #[arg(
+        short = 'O',
+        long,
+        conflicts_with_all = ["output", "select"],
+        help = "Save response body to file named by Content-Disposition or URL"
+    )]
+    pub remote_name: bool,
+    #[arg(
+        short = 'C',
+        long,
+        value_name = "offset",
+        value_parser = parse_continue_at,
+        help = "Resume download from offset, '-' to continue from size of the file"
+    )]
+    pub continue_at: Option<ContinueAt>,
+    #[arg(short, long, help = "Print response status line and headers before the body")]
+    pub include: bool,
+    #[arg(
+        short = 'D',
+        long,
+        value_name = "file",
+        help = "Save response status line and headers to file"
+    )]
+    pub dump_header: Option<PathBuf>,
+    #[arg(
+        short = 'I',
+        long,
+        conflicts_with_all = ["select", "output", "remote_name"],
+        help = "Print only response status line and headers, skip the body"
+    )]
+    pub head_only: bool,
+    #[arg(
+        short,
+        long,
+        value_name = "template",
+        help = "Print template after the body, e.g. '%{http_code} %{time_total}\\n', \
+                variables: http_code, content_type, size_download, url_effective, \
+                time_auth, time_connect, time_starttransfer, time_total"
+    )]
+    pub write_out: Option<String>,
+    #[arg(long, help = "Print time spent on token, connection and response to stderr")]
+    pub timing: bool,
+    #[arg(
+        long,
+        value_enum,
+        default_value = "text",
+        conflicts_with_all = [
+            "select", "output", "remote_name", "continue_at", "include", "head_only", "write_out",
+            "fail", "fail_with_body"
+        ],
+        help = "Print response body or one JSON document with request, response, timings and auth"
+    )]
+    pub output_format: OutputFormat,
+    #[arg(
+        long,
+        value_name = "file",
+        help = "Record token and API requests with responses to HAR file, appends to existing one"
+    )]
+    pub har: Option<PathBuf>,
+    #[arg(short = 'L', long, help = "Follow redirects")]
+    pub location: bool,
+    #[arg(
+        long,
+        value_name = "num",
+        default_value_t = 50,
+        help = "Maximum number of redirects followed with --location"
+    )]
+    pub max_redirs: u32,
+    #[arg(long, help = "Like --location, but send the token and cookies to other hosts too")]
+    pub location_trusted: bool,
+    #[arg(
+        long,
+        value_name = "num",
+        default_value_t = 0,
+        help = "Retry the token and target requests on connection failures, timeouts \
+                and retryable statuses"
+    )]
+    pub retry: u32,
+    #[arg(
+        long,
+        value_name = "duration",
+        value_parser = parse_duration,
+        help = "Delay before the first retry, doubled with jitter for each next one, \
+                Retry-After takes precedence, e.g. '2' seconds or '500ms' [default: 1s]"
+    )]
+    pub retry_delay: Option<Duration>,
+    #[arg(
+        long,
+        value_name = "duration",
+        value_parser = parse_duration,
+        help = "Don't start a retry later than this since the first attempt"
+    )]
+    pub retry_max_time: Option<Duration>,
+    #[arg(
+        long,
+        value_name = "status",
+        value_delimiter = ',',
+        help = "Statuses to retry separated by ',' [default: 408,429,500,502,503,504]"
+    )]
+    pub retry_status: Vec<u16>,
+    #[arg(long, help = "Retry POST and PATCH even if they may have reached the server")]
+    pub retry_all_methods: bool,
+    #[arg(
+        short = 'b',
+        long,
+        value_name = "data|file",
+        help = "Cookies 'name=value; name2=value2' to send, or Netscape cookie file to read, \
+                can be repeated"
+    )]
+    pub cookie: Vec<String>,
+    #[arg(
+        short = 'c',
+        long,
+        value_name = "file",
+        help = "Write all cookies to Netscape cookie file after the request"
+    )]
+    pub cookie_jar: Option<PathBuf>,
+    #[arg(long, help = "Ask for gzip, deflate, brotli or zstd compressed body and decode it")]
+    pub compressed: bool,
+    #[arg(long, help = "Don't decode compressed body, even with --compressed")]
+    pub raw: bool,
     #[arg(long, help = "Force use http 1.1")]
     pub http1: bool,
-    #[arg(long, help = "Request timeout")]
+    #[arg(long, conflicts_with = "max_time", help = "Same as --max-time, in milliseconds")]
     pub timeout_millis: Option<u64>,
-    #[arg(long, help = "Don't verify TLS")]
+    #[arg(
+        long,
+        value_name = "duration",
+        value_parser = parse_duration,
+        help = "Limit of name lookup, TCP and TLS handshake, fails with exit code 4"
+    )]
+    pub connect_timeout: Option<Duration>,
+    #[arg(
+        long,
+        value_name = "duration",
+        value_parser = parse_duration,
+        help = "Limit of waiting for the next part of the response, fails with exit code 5"
+    )]
+    pub read_timeout: Option<Duration>,
+    #[arg(
+        short = 'm',
+        long,
+        value_name = "duration",
+        value_parser = parse_duration,
+        help = "Limit of the whole request including the body, fails with exit code 5 \
+                [default: no limit]"
+    )]
+    pub max_time: Option<Duration>,
+    #[arg(
+        long,
+        value_name = "duration",
+        value_parser = parse_duration,
+        default_value = "60s",
+        help = "Limit of the token request to Keycloak, fails with exit code 5"
+    )]
+    pub auth_timeout: Duration,
+    #[arg(long, help = "Don't verify TLS of the target, Keycloak is always verified")]
     pub insecure: bool,
+    #[arg(
+        long,
+        value_name = "file",
+        help = "PEM bundle of CA certificates to trust instead of system ones"
+    )]
+    pub cacert: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "dir",
+        help = "Directory of PEM CA certificates to trust instead of system ones"
+    )]
+    pub capath: Option<PathBuf>,
+    #[arg(
+        short = 'E',
+        long,
+        value_name = "file[:password]",
+        help = "Client certificate: PEM with key or PKCS#12 with optional password"
+    )]
+    pub cert: Option<String>,
+    #[arg(
+        long,
+        value_name = "file",
+        requires = "cert",
+        help = "Private key of PEM client certificate if it's not in the --cert file"
+    )]
+    pub key: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "sha256//<base64>",
+        help = "Fail unless the server public key matches one of the pins separated by ';'"
+    )]
+    pub pinnedpubkey: Option<String>,
+    #[arg(
+        short = 'x',
+        long,
+        value_name = "[scheme://]host[:port]",
+        help = "Proxy for the token and target requests: http, https, socks5 or socks5h, \
+                '' to ignore HTTP_PROXY, HTTPS_PROXY and ALL_PROXY"
+    )]
+    pub proxy: Option<String>,
+    #[arg(
+        short = 'U',
+        long,
+        value_name = "user:password",
+        help = "Proxy credentials"
+    )]
+    pub proxy_user: Option<String>,
+    #[arg(
+        long,
+        value_name = "hosts",
+        help = "Hosts, domains or networks separated by ',' to connect without proxy, \
+                '*' for all, overrides NO_PROXY"
+    )]
+    pub noproxy: Option<String>,
+    #[arg(
+        long,
+        value_name = "host:port:addr[,addr]",
+        help = "Connect to the addresses instead of resolving the host and port, can be repeated"
+    )]
+    pub resolve: Vec<ResolveOverride>,
+    #[arg(
+        long,
+        value_name = "host1:port1:host2:port2",
+        help = "Connect to host2 and port2 instead of host1 and port1, empty parts match any \
+                or keep the original, can be repeated"
+    )]
+    pub connect_to: Vec<ConnectTo>,
+    #[cfg(unix)]
+    #[arg(
+        long,
+        value_name = "path",
+        conflicts_with_all = ["proxy", "resolve", "connect_to"],
+        help = "Send the request over Unix domain socket instead of TCP, the URL host is still \
+                sent in Host header"
+    )]
+    pub unix_socket: Option<PathBuf>,
 }
 
 #[derive(clap::Subcommand, Debug)]
+#[allow(clippy::large_enum_variant)] // parsed once, size doesn't matter
 pub enum KeycloakCommand {
     Add {
         #[arg(value_name = "Keycloak address")]
@@ -71,6 +364,40 @@ pub enum KeycloakCommand {
         password: String,
         #[arg(long, help = "Make this Keycloak default")]
         default: bool,
+        #[arg(long, help = "Base URL to resolve relative request URLs against")]
+        base_url: Option<String>,
+        #[arg(
+            name = "Header",
+            short = 'H',
+            long = "header",
+            help = "Default header sent with every request, for example: 'X-Tenant-Id: 42'"
+        )]
+        default_headers: Vec<String>,
+        #[arg(
+            long = "route",
+            help = "Host, '*.domain' or URL prefix, token is sent only to matching URLs"
+        )]
+        routes: Vec<String>,
+        #[arg(long, value_name = "file", help = "Default for --cacert")]
+        cacert: Option<PathBuf>,
+        #[arg(long, value_name = "dir", help = "Default for --capath")]
+        capath: Option<PathBuf>,
+        #[arg(long, value_name = "file[:password]", help = "Default for --cert")]
+        cert: Option<String>,
+        #[arg(long, value_name = "file", requires = "cert", help = "Default for --key")]
+        key: Option<PathBuf>,
+        #[arg(
+            long = "pinnedpubkey",
+            value_name = "sha256//<base64>",
+            help = "Default for --pinnedpubkey, can be repeated"
+        )]
+        pinned_public_keys: Vec<String>,
+        #[arg(long, value_name = "[scheme://]host[:port]", help = "Default for --proxy")]
+        proxy: Option<String>,
+        #[arg(long, value_name = "hosts", help = "Default for --noproxy")]
+        noproxy: Option<String>,
+        #[arg(long, help = "Keep cookies between requests in ~/.curlaut/cookies/<alias>.txt")]
+        persist_cookies: bool,
     },
     Remove {
         #[arg(help = "Keycloak Alias")]
@@ -82,3 +409,21 @@ pub enum KeycloakCommand {
     },
     List,
 }
+
+/// Seconds like curl, fractions allowed, or with units like '500ms' or '1m 30s'.
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    if let Ok(seconds) = value.parse::<f64>() {
+        return Duration::try_from_secs_f64(seconds).map_err(|err| err.to_string());
+    }
+    humantime::parse_duration(value).map_err(|err| format!("{err}, expected e.g. '2' or '500ms'"))
+}
+
+fn parse_continue_at(value: &str) -> Result<ContinueAt, String> {
+    match value {
+        "-" => Ok(ContinueAt::FileSize),
+        offset => offset
+            .parse()
+            .map(ContinueAt::Offset)
+            .map_err(|_| format!("expected number of bytes or '-', got '{offset}'")),
+    }
+}
@@ -0,0 +1,82 @@
+use crate::auth::auth_error::AuthError;
+use crate::keycloak::config_error::ConfigError;
+use crate::request::request_error::RequestError;
+
+/// Process exit codes, keep in sync with README.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CurlautExitCode {
+    Success = 0,
+    Error = 1,
+    // 2 is used by clap for invalid arguments
+    AuthFailure = 3,
+    ConnectionFailure = 4,
+    Timeout = 5,
+    HttpClientError = 6,
+    HttpServerError = 7,
+    ConfigError = 8,
+}
+
+impl CurlautExitCode {
+    pub fn from_error(err: &anyhow::Error) -> CurlautExitCode {
+        for cause in err.chain() {
+            if let Some(auth_error) = cause.downcast_ref::<AuthError>() {
+                return Self::from_auth_error(auth_error);
+            }
+            if let Some(request_error) = cause.downcast_ref::<RequestError>() {
+                return Self::from_request_error(request_error);
+            }
+            if cause.downcast_ref::<ConfigError>().is_some() {
+                return CurlautExitCode::ConfigError;
+            }
+        }
+        CurlautExitCode::Error
+    }
+
+    fn from_auth_error(err: &AuthError) -> CurlautExitCode {
+        match err {
+            AuthError::InvalidGrant { .. }
+            | AuthError::Rejected { .. }
+            | AuthError::Unreachable { .. }
+            | AuthError::BadResponse { .. } => CurlautExitCode::AuthFailure,
+            AuthError::Timeout { .. } => CurlautExitCode::Timeout,
+            AuthError::InvalidTokenUrl { .. } => CurlautExitCode::ConfigError,
+            AuthError::Client { .. } | AuthError::Output(_) => CurlautExitCode::Error,
+        }
+    }
+
+    fn from_request_error(err: &RequestError) -> CurlautExitCode {
+        match err {
+            RequestError::Resolve { .. }
+            | RequestError::Connect { .. }
+            | RequestError::ConnectTimeout { .. }
+            | RequestError::Body { .. }
+            | RequestError::Transport { .. }
+            | RequestError::PinnedPublicKey { .. } => CurlautExitCode::ConnectionFailure,
+            RequestError::Timeout { .. } => CurlautExitCode::Timeout,
+            RequestError::Status { status } if *status >= 500 => CurlautExitCode::HttpServerError,
+            RequestError::Status { .. } => CurlautExitCode::HttpClientError,
+            RequestError::InvalidHeader(_)
+            | RequestError::InvalidHeaderName(_)
+            | RequestError::InvalidHeaderValue(_)
+            | RequestError::NoHost(_)
+            | RequestError::Reroute(_)
+            | RequestError::Client(_)
+            | RequestError::Tls(_)
+            | RequestError::CertificateFile { .. }
+            | RequestError::InvalidCertificate { .. }
+            | RequestError::InvalidPin(_)
+            | RequestError::InvalidProxy { .. }
+            | RequestError::Build(_)
+            | RequestError::TooManyRedirects { .. }
+            | RequestError::Select(_)
+            | RequestError::NoFileName(_)
+            | RequestError::File { .. }
+            | RequestError::CookieFile { .. }
+            | RequestError::Output(_) => CurlautExitCode::Error,
+        }
+    }
+
+    pub fn code(self) -> i32 {
+        self as i32
+    }
+}
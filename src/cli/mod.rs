@@ -3,10 +3,18 @@ use std::path::PathBuf;
 
 pub mod auth_mgmt;
 pub mod clap_config;
+pub mod exit_code;
 pub mod request_executor;
+pub mod request_items;
 
 pub fn auth_config_file_path() -> anyhow::Result<PathBuf> {
     let auth_config_file_path: &str = ".curlaut/auth.yaml";
     let home_dir_path = std::env::home_dir().with_context(|| "Could not get home directory")?;
     Ok(home_dir_path.join(auth_config_file_path))
 }
+
+/// Persistent cookie jar of the Keycloak config, see `persist_cookies`.
+pub fn profile_cookie_jar_path(alias: &str) -> anyhow::Result<PathBuf> {
+    let home_dir_path = std::env::home_dir().with_context(|| "Could not get home directory")?;
+    Ok(home_dir_path.join(".curlaut/cookies").join(format!("{alias}.txt")))
+}
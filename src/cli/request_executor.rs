@@ -1,18 +1,29 @@
 use crate::auth::authenticator;
-use crate::auth::authenticator::JwtToken;
-use crate::cli::auth_config_file_path;
+use crate::auth::AuthInfo;
+use crate::cli::{auth_config_file_path, profile_cookie_jar_path};
 use crate::cli::clap_config::HttpRequestArgs;
+use crate::cli::request_items::{build_json_body, RequestItem};
+use crate::keycloak::config_error::ConfigError;
+use crate::keycloak::keycloak_config::KeycloakConfig;
 use crate::keycloak::keycloak_registry::KeycloakRegistry;
+use crate::output::har::HarRecorder;
+use crate::output::select::JsonSelector;
+use crate::output::write_out::WriteOut;
 use crate::output::CurlautOutput;
+use crate::request::cookies::CookieOptions;
+use crate::request::proxy::ProxyOptions;
+use crate::request::retry::RetryPolicy;
 use crate::request::request_executor::execute;
 use crate::request::request_spec::{
-    HttpRequestBody, HttpRequestHeaders, HttpRequestMethod, HttpRequestSpec,
+    DownloadFile, FollowRedirects, HttpDownload, HttpFailMode, HttpRequestBody,
+    HttpRequestHeaders, HttpRequestMethod, HttpRequestSpec, HttpTimeouts,
 };
-use anyhow::Context;
-use std::collections::HashMap;
+use crate::request::tls::{ClientCertificate, PublicKeyPin, TlsOptions};
+use anyhow::{bail, Context};
+use serde_json::Value;
 use std::fs::File;
 use std::io::Read;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use url::Url;
 
 pub fn execute_request(
@@ -23,50 +34,272 @@ pub fn execute_request(
     if args.verbose {
         io.enable_verbose();
     }
+    if let Some(pretty) = args.pretty {
+        io.set_pretty(pretty);
+    }
+    if args.include || args.head_only {
+        io.include_head();
+    }
+    if let Some(path) = &args.dump_header {
+        let file = File::create(path)
+            .with_context(|| format!("Can't create header file '{}'", path.display()))?;
+        io.dump_head(file);
+    }
     let auth_config_file_path = auth_config_file_path()?;
     let keycloak_registry = KeycloakRegistry::load_from_file(auth_config_file_path.as_path())?;
-    let keycloak_config = keycloak_registry
-        .get_default()
-        .with_context(|| "No default keycloak config")?;
-    let jwt = authenticator::get_jwt(&keycloak_config, io)?;
-    let request = build_request_spec(&args, method, jwt)?;
-    execute(request, io)?;
+    let keycloak_config = select_keycloak_config(&keycloak_registry, &args)?;
+    let url = resolve_url(&args.url, keycloak_config.base_url.as_deref())?;
+    if !keycloak_config.allows_url(&url) {
+        bail!(
+            "Keycloak '{}' is not allowed for {url}, its token is sent only to: {}",
+            keycloak_config.alias,
+            keycloak_config.routes_as_string()
+        );
+    }
+    // invalid arguments are reported before the token is requested
+    let mut request = build_request_spec(&args, url, method)?;
+    request.token_routes = keycloak_config.routes.clone();
+    request.tls = tls_options(&args, keycloak_config)?;
+    request.proxy = proxy_options(&args, keycloak_config);
+    request.retry = retry_policy(&args);
+    request.cookies.persistent_jar = keycloak_config
+        .persist_cookies
+        .then(|| profile_cookie_jar_path(&keycloak_config.alias))
+        .transpose()?;
+    request.headers = request
+        .headers
+        .with_defaults(keycloak_config.get_default_headers()?);
+    let auth_started = Instant::now();
+    let auth_timeout = args.auth_timeout;
+    let mut har = args.har.clone().map(HarRecorder::new);
+    let jwt = authenticator::get_jwt(
+        keycloak_config,
+        &request.tls,
+        &request.proxy,
+        &request.retry,
+        auth_timeout,
+        har.as_mut(),
+        io,
+    )?;
+    request.auth_info = Some(AuthInfo {
+        alias: keycloak_config.alias.clone(),
+        token_source: jwt.source,
+        time: auth_started.elapsed(),
+    });
+    request.authorization = Some(Box::new(jwt));
+    execute(request, har.as_mut(), io)?;
     Ok(())
 }
 
+fn select_keycloak_config<'a>(
+    keycloak_registry: &'a KeycloakRegistry,
+    args: &HttpRequestArgs,
+) -> anyhow::Result<&'a KeycloakConfig> {
+    if let Some(alias) = &args.auth {
+        return keycloak_registry
+            .find_keycloak(alias)
+            .ok_or_else(|| ConfigError::NotFound(alias.to_owned()).into());
+    }
+    Url::parse(&args.url)
+        .ok()
+        .and_then(|url| keycloak_registry.find_keycloak_for_url(&url))
+        .or_else(|| keycloak_registry.get_default())
+        .with_context(|| "No keycloak config matches the URL and no default keycloak config")
+}
+
 fn build_request_spec(
     args: &HttpRequestArgs,
+    mut url: Url,
     method: HttpRequestMethod,
-    auth: JwtToken,
 ) -> anyhow::Result<HttpRequestSpec> {
+    let items = args
+        .items
+        .iter()
+        .map(|item| RequestItem::parse(item))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    for query in &args.query {
+        let (name, value) = query
+            .split_once('=')
+            .with_context(|| format!("Invalid query parameter '{query}', expected 'name=value'"))?;
+        url.query_pairs_mut().append_pair(name, value);
+    }
+    let mut header_items = Vec::new();
+    let mut fields = Vec::new();
+    for item in items {
+        match item {
+            RequestItem::Header(header) => header_items.push(header),
+            RequestItem::Query(name, value) => {
+                url.query_pairs_mut().append_pair(name, value);
+            }
+            RequestItem::Field(path, value) => fields.push((path, value)),
+        }
+    }
+    if args.get {
+        append_fields_to_query(&mut url, fields)?;
+        fields = Vec::new();
+    }
+    let headers = parse_headers(args.headers.iter().map(|s| s.as_str()).chain(header_items))?;
     Ok(HttpRequestSpec {
-        url: Url::parse(&args.url)?,
+        url,
         method,
-        headers: parse_headers(args.headers.iter().map(|s| s.as_str()).collect()),
-        body: get_body(args)?,
-        authorization: Box::new(auth),
+        headers,
+        body: get_body(args, fields)?,
+        authorization: None,
+        token_routes: Vec::new(),
         http1: args.http1,
-        timeout: args
-            .timeout_millis
-            .map(Duration::from_millis)
-            .unwrap_or(Duration::from_secs(60)),
+        timeouts: HttpTimeouts {
+            connect: args.connect_timeout,
+            read: args.read_timeout,
+            // no limit by default, a streamed download may take as long as it needs
+            total: args.max_time.or(args.timeout_millis.map(Duration::from_millis)),
+        },
+        tls: TlsOptions::default(),
+        proxy: ProxyOptions::default(),
+        retry: RetryPolicy::default(),
+        cookies: CookieOptions {
+            cookies: args.cookie.clone(),
+            cookie_jar: args.cookie_jar.clone(),
+            persistent_jar: None,
+        },
+        compressed: args.compressed,
+        raw: args.raw,
+        resolve: args.resolve.clone(),
+        connect_to: args.connect_to.clone(),
+        #[cfg(unix)]
+        unix_socket: args.unix_socket.clone(),
+        location: (args.location || args.location_trusted).then_some(FollowRedirects {
+            max_redirects: args.max_redirs,
+            trusted: args.location_trusted,
+        }),
+        select: args.select.as_deref().map(JsonSelector::parse).transpose()?,
+        raw_output: args.raw_output,
+        download: get_download(args)?,
+        head_only: args.head_only,
+        write_out: args.write_out.as_deref().map(WriteOut::parse).transpose()?,
+        timing: args.timing,
+        auth_info: None,
+        output_format: args.output_format,
+        fail: match (args.fail, args.fail_with_body) {
+            (_, true) => HttpFailMode::WithBody,
+            (true, false) => HttpFailMode::WithoutBody,
+            (false, false) => HttpFailMode::Never,
+        },
+    })
+}
+
+/// Command line options override defaults of the Keycloak config one by one,
+/// `--key` is taken only with `--cert`.
+fn tls_options(args: &HttpRequestArgs, config: &KeycloakConfig) -> anyhow::Result<TlsOptions> {
+    let client_cert = match &args.cert {
+        Some(cert) => Some(ClientCertificate::parse(cert, args.key.clone())),
+        None => config
+            .tls
+            .cert
+            .as_deref()
+            .map(|cert| ClientCertificate::parse(cert, config.tls.key.clone())),
+    };
+    let pinned_public_keys = match &args.pinnedpubkey {
+        Some(pins) => PublicKeyPin::parse_list(pins)?,
+        None => config.get_pinned_public_keys()?,
+    };
+    Ok(TlsOptions {
         insecure: args.insecure,
+        ca_cert: args.cacert.clone().or_else(|| config.tls.ca_cert.clone()),
+        ca_path: args.capath.clone().or_else(|| config.tls.ca_path.clone()),
+        client_cert,
+        pinned_public_keys,
     })
 }
 
-fn parse_headers(headers: Vec<&str>) -> HttpRequestHeaders {
-    let map = headers
-        .iter()
-        .map(|header| header.split_once(":"))
-        .flatten()
-        .fold(HashMap::new(), |mut acc, (key, value)| {
-            acc.insert(key, value);
-            acc
-        });
-    HttpRequestHeaders(map)
+fn retry_policy(args: &HttpRequestArgs) -> RetryPolicy {
+    let defaults = RetryPolicy::default();
+    RetryPolicy {
+        retries: args.retry,
+        delay: args.retry_delay.unwrap_or(defaults.delay),
+        max_time: args.retry_max_time,
+        statuses: if args.retry_status.is_empty() {
+            defaults.statuses
+        } else {
+            args.retry_status.clone()
+        },
+        all_methods: args.retry_all_methods,
+    }
 }
 
-fn get_body(args: &HttpRequestArgs) -> anyhow::Result<HttpRequestBody> {
+fn proxy_options(args: &HttpRequestArgs, config: &KeycloakConfig) -> ProxyOptions {
+    ProxyOptions {
+        proxy: args.proxy.clone().or_else(|| config.proxy.clone()),
+        proxy_user: args.proxy_user.clone(),
+        no_proxy: args.noproxy.clone().or_else(|| config.no_proxy.clone()),
+    }
+}
+
+fn resolve_url(url: &str, base_url: Option<&Url>) -> anyhow::Result<Url> {
+    match (Url::parse(url), base_url) {
+        (Ok(url), _) => Ok(url),
+        (Err(url::ParseError::RelativeUrlWithoutBase), Some(base_url)) => {
+            // append to the base path instead of Url::join, which replaces it for '/path'
+            let base = base_url.as_str().trim_end_matches('/');
+            let path = url.trim_start_matches('/');
+            Url::parse(&format!("{base}/{path}"))
+                .with_context(|| format!("Invalid URL '{url}' relative to '{base_url}'"))
+        }
+        (Err(err), _) => Err(err).with_context(|| format!("Invalid URL '{url}'")),
+    }
+}
+
+fn append_fields_to_query(url: &mut Url, fields: Vec<(&str, Value)>) -> anyhow::Result<()> {
+    for (name, value) in fields {
+        let values = match value {
+            Value::Array(values) => values,
+            value => vec![value],
+        };
+        for value in values {
+            let value = match value {
+                Value::Null => String::new(),
+                Value::String(s) => s,
+                Value::Bool(_) | Value::Number(_) => value.to_string(),
+                Value::Array(_) | Value::Object(_) => {
+                    bail!("Request item '{name}' can't be sent as query parameter")
+                }
+            };
+            url.query_pairs_mut().append_pair(name, &value);
+        }
+    }
+    Ok(())
+}
+
+fn get_download(args: &HttpRequestArgs) -> anyhow::Result<Option<HttpDownload>> {
+    let file = match (&args.output, args.remote_name) {
+        (Some(path), _) => DownloadFile::Path(path.clone()),
+        (None, true) => DownloadFile::RemoteName,
+        (None, false) if args.continue_at.is_some() => {
+            bail!("--continue-at requires --output or --remote-name")
+        }
+        (None, false) => return Ok(None),
+    };
+    Ok(Some(HttpDownload {
+        file,
+        continue_at: args.continue_at,
+    }))
+}
+
+fn parse_headers<'a>(headers: impl Iterator<Item = &'a str>) -> anyhow::Result<HttpRequestHeaders> {
+    let mut parsed = HttpRequestHeaders::new();
+    for header in headers {
+        parsed.add_parsed(header)?;
+    }
+    Ok(parsed)
+}
+
+fn get_body(args: &HttpRequestArgs, fields: Vec<(&str, Value)>) -> anyhow::Result<HttpRequestBody> {
+    if !fields.is_empty() {
+        if args.json_body.is_some() || args.json_body_file.is_some() {
+            bail!("Request items with body fields can't be combined with --json-body or --json-body-file");
+        }
+        let json = build_json_body(fields)?;
+        return Ok(HttpRequestBody::Json(json.to_string()));
+    }
     args.json_body
         .as_ref()
         .map(|json| Ok(HttpRequestBody::Json(json.to_owned())))
@@ -87,5 +320,5 @@ fn get_body_from_file(body_file_path: &str) -> anyhow::Result<HttpRequestBody> {
                 .with_context(|| "Can't read body file")?;
             Ok(buffer)
         })
-        .map(|buffer| HttpRequestBody::Json(buffer))
+        .map(HttpRequestBody::Json)
 }
@@ -0,0 +1,133 @@
+use anyhow::{bail, Context};
+use serde_json::{Map, Value};
+use std::fs::read_to_string;
+
+/// HTTPie-style request item given after the URL on the command line.
+#[derive(Debug, PartialEq)]
+pub enum RequestItem<'a> {
+    /// `Header:value`, `Header:` or `Header;`, kept as is to be parsed as a header
+    Header(&'a str),
+    /// `name==value`
+    Query(&'a str, &'a str),
+    /// `path=value`, `path:=<raw json>` or `path@file`
+    Field(&'a str, Value),
+}
+
+// longer separators go first so `:=` wins over `:` and `==` over `=`
+const SEPARATORS: [&str; 5] = [":=", "==", "=", "@", ":"];
+
+impl<'a> RequestItem<'a> {
+    pub fn parse(item: &'a str) -> anyhow::Result<RequestItem<'a>> {
+        if let Some(name) = item.strip_suffix(';')
+            && !name.contains([':', '=', '@'])
+        {
+            return Ok(RequestItem::Header(item));
+        }
+        let position = item
+            .find([':', '=', '@'])
+            .with_context(|| format!("Invalid request item '{item}'"))?;
+        let (key, rest) = item.split_at(position);
+        if key.is_empty() {
+            bail!("Invalid request item '{item}': missing name");
+        }
+        let separator = SEPARATORS
+            .iter()
+            .find(|sep| rest.starts_with(*sep))
+            .expect("separator must match found position");
+        let value = &rest[separator.len()..];
+        let item = match *separator {
+            ":" => RequestItem::Header(item),
+            "==" => RequestItem::Query(key, value),
+            "=" => RequestItem::Field(key, Value::String(value.to_owned())),
+            ":=" => {
+                let json = serde_json::from_str(value)
+                    .with_context(|| format!("Invalid JSON in request item '{item}'"))?;
+                RequestItem::Field(key, json)
+            }
+            "@" => {
+                let content = read_to_string(value).with_context(|| {
+                    format!("Can't read file '{value}' of request item '{key}'")
+                })?;
+                RequestItem::Field(key, Value::String(content))
+            }
+            _ => unreachable!(),
+        };
+        Ok(item)
+    }
+}
+
+/// Builds a JSON object from field items, `a[b][c]` paths produce nested objects,
+/// `a[]` appends to an array and `a[0]` sets an existing array element or appends the next one.
+pub fn build_json_body(fields: Vec<(&str, Value)>) -> anyhow::Result<Value> {
+    let mut root = Value::Object(Map::new());
+    for (path, value) in fields {
+        let segments = parse_path(path)?;
+        set_value(&mut root, &segments, value)
+            .with_context(|| format!("Conflicting request item path '{path}'"))?;
+    }
+    Ok(root)
+}
+
+#[derive(Debug)]
+enum PathSegment<'a> {
+    Key(&'a str),
+    Index(usize),
+    Append,
+}
+
+fn parse_path(path: &str) -> anyhow::Result<Vec<PathSegment<'_>>> {
+    let (head, mut rest) = path.split_at(path.find('[').unwrap_or(path.len()));
+    if head.is_empty() {
+        bail!("Invalid request item path '{path}', it must start with a key");
+    }
+    let mut segments = vec![PathSegment::Key(head)];
+    while !rest.is_empty() {
+        let (segment, tail) = rest
+            .strip_prefix('[')
+            .and_then(|s| s.split_once(']'))
+            .with_context(|| format!("Invalid request item path '{path}'"))?;
+        segments.push(match segment {
+            "" => PathSegment::Append,
+            _ => match segment.parse::<usize>() {
+                Ok(index) => PathSegment::Index(index),
+                Err(_) => PathSegment::Key(segment),
+            },
+        });
+        rest = tail;
+    }
+    Ok(segments)
+}
+
+fn set_value(target: &mut Value, path: &[PathSegment], value: Value) -> anyhow::Result<()> {
+    let Some((segment, rest)) = path.split_first() else {
+        *target = value;
+        return Ok(());
+    };
+    if target.is_null() {
+        *target = match segment {
+            PathSegment::Key(_) => Value::Object(Map::new()),
+            PathSegment::Index(_) | PathSegment::Append => Value::Array(Vec::new()),
+        };
+    }
+    let next = match (segment, target) {
+        (PathSegment::Key(key), Value::Object(map)) => {
+            map.entry(key.to_string()).or_insert(Value::Null)
+        }
+        (PathSegment::Append, Value::Array(array)) => {
+            array.push(Value::Null);
+            array.last_mut().expect("just pushed")
+        }
+        // like HTTPie, an index may overwrite an element or append one, but not leave gaps
+        (PathSegment::Index(index), Value::Array(array)) if *index <= array.len() => {
+            if *index == array.len() {
+                array.push(Value::Null);
+            }
+            &mut array[*index]
+        }
+        (PathSegment::Index(index), Value::Array(array)) => {
+            bail!("Index {index} is beyond the end of array of {} elements", array.len())
+        }
+        (segment, _) => bail!("Can't apply {segment:?} here"),
+    };
+    set_value(next, rest, value)
+}
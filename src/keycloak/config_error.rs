@@ -0,0 +1,63 @@
+use crate::request::request_error::RequestError;
+use std::path::PathBuf;
+
+/// Invalid Keycloak configuration or failure to read/write the config file.
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("Can't open config file {path} for read")]
+    Open {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("Can't parse config file {path}")]
+    Parse {
+        path: PathBuf,
+        #[source]
+        source: serde_yaml::Error,
+    },
+    #[error("Can't open config file {path} for write")]
+    OpenForWrite {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("Can't write config file {path}")]
+    Write {
+        path: PathBuf,
+        #[source]
+        source: serde_yaml::Error,
+    },
+    #[error("Alias `{0}` already exists")]
+    DuplicateAlias(String),
+    #[error("Keycloak with alias `{0}` not found")]
+    NotFound(String),
+    #[error("Config property '{0}' must not be empty")]
+    EmptyProperty(&'static str),
+    #[error("Invalid URL '{url}'")]
+    InvalidUrl {
+        url: String,
+        #[source]
+        source: url::ParseError,
+    },
+    #[error("Invalid default header of Keycloak '{alias}'")]
+    InvalidHeader {
+        alias: String,
+        #[source]
+        source: Box<RequestError>,
+    },
+    #[error("Invalid proxy of Keycloak '{alias}'")]
+    InvalidProxy {
+        alias: String,
+        #[source]
+        source: Box<RequestError>,
+    },
+    #[error("Invalid pinned public key of Keycloak '{alias}'")]
+    InvalidPin {
+        alias: String,
+        #[source]
+        source: Box<RequestError>,
+    },
+    #[error("Invalid route '{route}', {reason}")]
+    InvalidRoute { route: String, reason: &'static str },
+}
@@ -1,7 +1,12 @@
-use anyhow::{bail, Context};
+use crate::keycloak::config_error::ConfigError;
+use crate::keycloak::keycloak_route::KeycloakRoute;
+use crate::request::proxy::parse_proxy_url;
+use crate::request::request_spec::HttpRequestHeaders;
+use crate::request::tls::PublicKeyPin;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt::Formatter;
 use std::ops::Deref;
+use std::path::PathBuf;
 use url::Url;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -15,6 +20,36 @@ pub struct KeycloakConfig {
     pub username: String,
     pub password: String,
     pub(in crate::keycloak) default: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<MyUrl>, // relative request urls are resolved against it
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub default_headers: Vec<String>, // 'Name: value', sent before headers from cli
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub routes: Vec<KeycloakRoute>, // if not empty, token is sent only to matching urls
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>, // for token and target requests, overridden by cli
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub no_proxy: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub persist_cookies: bool, // cookies of requests are kept in ~/.curlaut/cookies/<alias>.txt
+    #[serde(flatten)]
+    pub tls: KeycloakTls,
+}
+
+/// TLS defaults for the token request and requests using the config, overridden by cli options.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeycloakTls {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_cert: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_path: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cert: Option<String>, // 'file[:password]', PEM or PKCS#12
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pinned_public_keys: Vec<String>, // 'sha256//<base64>' of target servers
 }
 
 impl std::fmt::Display for KeycloakConfig {
@@ -26,13 +61,47 @@ impl std::fmt::Display for KeycloakConfig {
         write!(
             f,
             ":\n  [ url={}, realm={}, client_id={}, client_secret={}, username={}, password={} ]",
-            self.url.0.to_string(),
+            self.url.0,
             self.realm,
             self.client_id,
             self.client_secret,
             self.username,
             self.password
-        )
+        )?;
+        if let Some(base_url) = &self.base_url {
+            write!(f, "\n  base_url={}", base_url.0)?;
+        }
+        if !self.default_headers.is_empty() {
+            write!(f, "\n  default_headers={:?}", self.default_headers)?;
+        }
+        if !self.routes.is_empty() {
+            write!(f, "\n  routes=[ {} ]", self.routes_as_string())?;
+        }
+        if let Some(proxy) = &self.proxy {
+            write!(f, "\n  proxy={proxy}")?;
+        }
+        if let Some(no_proxy) = &self.no_proxy {
+            write!(f, "\n  no_proxy={no_proxy}")?;
+        }
+        if self.persist_cookies {
+            write!(f, "\n  persist_cookies=true")?;
+        }
+        if let Some(ca_cert) = &self.tls.ca_cert {
+            write!(f, "\n  ca_cert={}", ca_cert.display())?;
+        }
+        if let Some(ca_path) = &self.tls.ca_path {
+            write!(f, "\n  ca_path={}", ca_path.display())?;
+        }
+        if let Some(cert) = &self.tls.cert {
+            write!(f, "\n  cert={cert}")?;
+        }
+        if let Some(key) = &self.tls.key {
+            write!(f, "\n  key={}", key.display())?;
+        }
+        if !self.tls.pinned_public_keys.is_empty() {
+            write!(f, "\n  pinned_public_keys={:?}", self.tls.pinned_public_keys)?;
+        }
+        Ok(())
     }
 }
 
@@ -67,6 +136,7 @@ impl<'de> Deserialize<'de> for MyUrl {
 }
 
 impl KeycloakConfig {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         alias: &str,
         url: &str,
@@ -76,7 +146,14 @@ impl KeycloakConfig {
         username: &str,
         password: &str,
         default: bool,
-    ) -> anyhow::Result<KeycloakConfig> {
+        base_url: Option<&str>,
+        default_headers: &[String],
+        routes: &[String],
+        proxy: Option<&str>,
+        no_proxy: Option<&str>,
+        persist_cookies: bool,
+        tls: KeycloakTls,
+    ) -> Result<KeycloakConfig, ConfigError> {
         let config = KeycloakConfig {
             alias: Self::require_non_empty("alias", alias)?,
             url: MyUrl(Self::require_url(url)?),
@@ -86,18 +163,84 @@ impl KeycloakConfig {
             username: Self::require_non_empty("username", username)?,
             password: Self::require_non_empty("password", password)?,
             default,
+            base_url: base_url.map(Self::require_url).transpose()?.map(MyUrl),
+            default_headers: default_headers.to_vec(),
+            routes: routes
+                .iter()
+                .map(|route| KeycloakRoute::parse(route))
+                .collect::<Result<_, _>>()?,
+            proxy: proxy.map(|proxy| proxy.to_owned()),
+            no_proxy: no_proxy.map(|no_proxy| no_proxy.to_owned()),
+            persist_cookies,
+            tls,
         };
+        config.get_default_headers()?; // validate
+        config.get_pinned_public_keys()?; // validate
+        if let Some(proxy) = &config.proxy {
+            parse_proxy_url(proxy).map_err(|source| ConfigError::InvalidProxy {
+                alias: config.alias.clone(),
+                source: Box::new(source),
+            })?;
+        }
         Ok(config)
     }
 
-    fn require_non_empty(property: &'static str, value: &str) -> anyhow::Result<String> {
+    fn require_non_empty(property: &'static str, value: &str) -> Result<String, ConfigError> {
         if value.is_empty() {
-            bail!("Config property '{}' must not be empty", property);
+            return Err(ConfigError::EmptyProperty(property));
         }
         Ok(value.to_owned())
     }
 
-    fn require_url(url: &str) -> anyhow::Result<Url> {
-        Url::parse(url).with_context(|| format!("Invalid URL '{}'", url))
+    fn require_url(url: &str) -> Result<Url, ConfigError> {
+        Url::parse(url).map_err(|source| ConfigError::InvalidUrl {
+            url: url.to_owned(),
+            source,
+        })
+    }
+
+    /// Whether the token of this config may be sent to the url.
+    pub fn allows_url(&self, url: &Url) -> bool {
+        self.routes.is_empty() || self.matches_url(url)
+    }
+
+    /// Whether any of the routes matches the url, never matches without routes.
+    pub fn matches_url(&self, url: &Url) -> bool {
+        self.routes.iter().any(|route| route.matches(url))
+    }
+
+    pub fn routes_as_string(&self) -> String {
+        self.routes
+            .iter()
+            .map(|route| route.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Public key pins parsed to be checked for servers of requests using this config.
+    pub fn get_pinned_public_keys(&self) -> Result<Vec<PublicKeyPin>, ConfigError> {
+        self.tls
+            .pinned_public_keys
+            .iter()
+            .map(|pin| PublicKeyPin::parse(pin))
+            .collect::<Result<_, _>>()
+            .map_err(|source| ConfigError::InvalidPin {
+                alias: self.alias.clone(),
+                source: Box::new(source),
+            })
+    }
+
+    /// Default headers parsed to be sent with every request using this config.
+    pub fn get_default_headers(&self) -> Result<HttpRequestHeaders, ConfigError> {
+        let mut headers = HttpRequestHeaders::new();
+        for header in &self.default_headers {
+            headers
+                .add_parsed(header)
+                .map_err(|source| ConfigError::InvalidHeader {
+                    alias: self.alias.clone(),
+                    source: Box::new(source),
+                })?;
+        }
+        Ok(headers)
     }
 }
@@ -1,8 +1,9 @@
+use crate::keycloak::config_error::ConfigError;
 use crate::keycloak::keycloak_config::KeycloakConfig;
-use anyhow::{bail, Context};
 use linked_hash_map::LinkedHashMap;
 use std::fs::{File, OpenOptions};
 use std::path::Path;
+use url::Url;
 
 pub struct KeycloakRegistry {
     keycloak_by_alias: LinkedHashMap<String, KeycloakConfig>, // alias -> keycloak
@@ -10,10 +11,16 @@ pub struct KeycloakRegistry {
 }
 
 impl KeycloakRegistry {
-    pub fn load_from_file(path: &Path) -> anyhow::Result<KeycloakRegistry> {
-        let config_file = File::open(path).with_context(|| "Can't open config file for read")?;
+    pub fn load_from_file(path: &Path) -> Result<KeycloakRegistry, ConfigError> {
+        let config_file = File::open(path).map_err(|source| ConfigError::Open {
+            path: path.to_owned(),
+            source,
+        })?;
         let keycloak_configs: Vec<KeycloakConfig> =
-            serde_yaml::from_reader(config_file).with_context(|| "Can't parse config file")?;
+            serde_yaml::from_reader(config_file).map_err(|source| ConfigError::Parse {
+                path: path.to_owned(),
+                source,
+            })?;
         let mut keycloaks = Self::new_empty();
         let mut default_alias = None;
         for config in keycloak_configs {
@@ -26,15 +33,22 @@ impl KeycloakRegistry {
         Ok(keycloaks)
     }
 
-    pub fn save_to_file(self, path: &Path) -> anyhow::Result<()> {
+    pub fn save_to_file(self, path: &Path) -> Result<(), ConfigError> {
         let config_file = OpenOptions::new()
             .write(true)
             .truncate(true)
             .open(path)
-            .with_context(|| "Can't open config file for write")?;
+            .map_err(|source| ConfigError::OpenForWrite {
+                path: path.to_owned(),
+                source,
+            })?;
         let keycloak_configs: Vec<&KeycloakConfig> = self.keycloak_by_alias.values().collect();
-        serde_yaml::to_writer(config_file, &keycloak_configs)
-            .with_context(|| "Can't write config file")?;
+        serde_yaml::to_writer(config_file, &keycloak_configs).map_err(|source| {
+            ConfigError::Write {
+                path: path.to_owned(),
+                source,
+            }
+        })?;
         Ok(())
     }
 
@@ -45,12 +59,12 @@ impl KeycloakRegistry {
         }
     }
 
-    pub fn add_keycloak(&mut self, config: KeycloakConfig) -> anyhow::Result<()> {
+    pub fn add_keycloak(&mut self, config: KeycloakConfig) -> Result<(), ConfigError> {
         log::info!("Adding keycloak for config `{:?}`", config);
         let alias = config.alias.to_owned();
         let is_default = config.default;
         if self.keycloak_by_alias.contains_key(&config.alias) {
-            bail!("Alias `{}` already exists", alias);
+            return Err(ConfigError::DuplicateAlias(alias));
         }
         self.keycloak_by_alias.insert(alias.to_owned(), config);
         if is_default {
@@ -68,6 +82,13 @@ impl KeycloakRegistry {
         self.keycloak_by_alias.get(alias)
     }
 
+    /// First keycloak (in config order) having a route matching the url.
+    pub fn find_keycloak_for_url(&self, url: &Url) -> Option<&KeycloakConfig> {
+        self.keycloak_by_alias
+            .values()
+            .find(|config| config.matches_url(url))
+    }
+
     pub fn get_all(&self) -> Vec<&KeycloakConfig> {
         self.keycloak_by_alias.values().collect()
     }
@@ -77,7 +98,7 @@ impl KeycloakRegistry {
         self.keycloak_by_alias.get(default)
     }
 
-    pub fn set_default(&mut self, new_default_alias: &str) -> anyhow::Result<()> {
+    pub fn set_default(&mut self, new_default_alias: &str) -> Result<(), ConfigError> {
         let is_default_the_same = self
             .default_alias
             .as_ref()
@@ -92,7 +113,7 @@ impl KeycloakRegistry {
             let new_default = self
                 .keycloak_by_alias
                 .get_mut(new_default_alias)
-                .with_context(|| format!("Keycloak with alias `{new_default_alias}` not found`"))?;
+                .ok_or_else(|| ConfigError::NotFound(new_default_alias.to_owned()))?;
             new_default.default = true;
             // set cached value
             self.default_alias = Some(new_default_alias.to_string());
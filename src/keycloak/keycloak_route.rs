@@ -0,0 +1,88 @@
+use crate::keycloak::config_error::ConfigError;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::{Display, Formatter};
+use url::Url;
+
+/// Rule telling which request URLs a Keycloak config is used for:
+/// host (`api.internal`), host wildcard (`*.stage.internal`) or URL prefix (`https://gw/api/`).
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeycloakRoute {
+    Host(String),
+    HostSuffix(String), // `*.stage.internal` is kept as `.stage.internal`
+    UrlPrefix(Url),
+}
+
+impl KeycloakRoute {
+    pub fn parse(route: &str) -> Result<KeycloakRoute, ConfigError> {
+        let route = route.trim();
+        if route.is_empty() {
+            return Err(ConfigError::EmptyProperty("route"));
+        }
+        if route.contains("://") {
+            let url = Url::parse(route).map_err(|source| ConfigError::InvalidUrl {
+                url: route.to_owned(),
+                source,
+            })?;
+            return Ok(KeycloakRoute::UrlPrefix(url));
+        }
+        let invalid_route = |reason| ConfigError::InvalidRoute {
+            route: route.to_owned(),
+            reason,
+        };
+        if route.contains(['/', ':', '?', '#']) {
+            return Err(invalid_route("expected host, '*.domain' or URL prefix"));
+        }
+        match route.to_ascii_lowercase().strip_prefix('*') {
+            Some(suffix) if suffix.starts_with('.') && suffix.len() > 1 => {
+                Ok(KeycloakRoute::HostSuffix(suffix.to_owned()))
+            }
+            Some(_) => Err(invalid_route("wildcard must look like '*.domain'")),
+            None => Ok(KeycloakRoute::Host(route.to_ascii_lowercase())),
+        }
+    }
+
+    pub fn matches(&self, url: &Url) -> bool {
+        let host = url.host_str().map(|host| host.to_ascii_lowercase());
+        match self {
+            KeycloakRoute::Host(expected) => host.as_deref() == Some(expected),
+            KeycloakRoute::HostSuffix(suffix) => host.is_some_and(|host| host.ends_with(suffix)),
+            KeycloakRoute::UrlPrefix(prefix) => {
+                let prefix = prefix.as_str();
+                let Some(rest) = url.as_str().strip_prefix(prefix) else {
+                    return false;
+                };
+                // 'https://gw/api' must not match 'https://gw/api-admin' or 'https://gw/apix'
+                prefix.ends_with('/') || rest.is_empty() || rest.starts_with(['/', '?', '#'])
+            }
+        }
+    }
+}
+
+impl Display for KeycloakRoute {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeycloakRoute::Host(host) => write!(f, "{host}"),
+            KeycloakRoute::HostSuffix(suffix) => write!(f, "*{suffix}"),
+            KeycloakRoute::UrlPrefix(url) => write!(f, "{url}"),
+        }
+    }
+}
+
+impl Serialize for KeycloakRoute {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for KeycloakRoute {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        KeycloakRoute::parse(&s).map_err(serde::de::Error::custom)
+    }
+}
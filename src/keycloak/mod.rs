@@ -1,2 +1,4 @@
+pub mod config_error;
 pub mod keycloak_config;
 pub mod keycloak_registry;
+pub mod keycloak_route;
@@ -1,4 +1,4 @@
-mod auth;
+pub mod auth;
 pub mod cli;
 pub mod keycloak;
 pub mod output;
@@ -0,0 +1,137 @@
+use crate::auth::AuthInfo;
+use crate::output::pretty;
+use crate::output::CurlautOutput;
+use crate::request::timings::HttpTimings;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use reqwest::header::{HeaderMap, AUTHORIZATION};
+use reqwest::{Method, StatusCode};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::io::Write;
+use std::time::Duration;
+use url::Url;
+
+/// `--output-format` option: how the result of the call is printed.
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// response body as is, the rest goes to stderr
+    Text,
+    /// one JSON document with request, response, timings and auth
+    Json,
+}
+
+/// Everything about one call for `--output-format json`.
+#[derive(Debug, Serialize)]
+pub struct JsonEnvelope {
+    pub request: EnvelopeRequest,
+    pub response: EnvelopeResponse,
+    pub timings: EnvelopeTimings,
+    pub auth: Option<EnvelopeAuth>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EnvelopeRequest {
+    method: String,
+    url: String,
+    headers: Map<String, Value>,
+}
+
+/// Body is put to `body_json` if it's JSON, otherwise to `body` encoded with base64.
+#[derive(Debug, Serialize)]
+pub struct EnvelopeResponse {
+    status: u16,
+    headers: Map<String, Value>,
+    body: Option<String>,
+    body_json: Option<Value>,
+}
+
+/// Seconds, `null` for phases which didn't happen.
+#[derive(Debug, Serialize)]
+pub struct EnvelopeTimings {
+    auth: Option<f64>,
+    connect: Option<f64>,
+    start_transfer: Option<f64>,
+    total: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EnvelopeAuth {
+    alias: String,
+    token_source: String,
+}
+
+impl EnvelopeRequest {
+    /// The token is not included, `Authorization` value is redacted.
+    pub fn new(method: &Method, url: &Url, headers: &HeaderMap) -> Self {
+        let mut headers = headers_to_json(headers);
+        if let Some(authorization) = headers.get_mut(AUTHORIZATION.as_str()) {
+            *authorization = Value::from("<redacted>");
+        }
+        Self {
+            method: method.to_string(),
+            url: url.to_string(),
+            headers,
+        }
+    }
+}
+
+impl EnvelopeResponse {
+    pub fn new(status: StatusCode, headers: &HeaderMap, body: &[u8]) -> Self {
+        let body_json = serde_json::from_slice(body).ok();
+        let body = match body_json {
+            None if !body.is_empty() => Some(BASE64.encode(body)),
+            _ => None,
+        };
+        Self {
+            status: status.as_u16(),
+            headers: headers_to_json(headers),
+            body,
+            body_json,
+        }
+    }
+}
+
+impl From<&HttpTimings> for EnvelopeTimings {
+    fn from(timings: &HttpTimings) -> Self {
+        Self {
+            auth: timings.auth.as_ref().map(Duration::as_secs_f64),
+            connect: timings.connect.as_ref().map(Duration::as_secs_f64),
+            start_transfer: timings.start_transfer.as_ref().map(Duration::as_secs_f64),
+            total: timings.total.as_secs_f64(),
+        }
+    }
+}
+
+impl From<&AuthInfo> for EnvelopeAuth {
+    fn from(auth: &AuthInfo) -> Self {
+        Self {
+            alias: auth.alias.clone(),
+            token_source: auth.token_source.to_string(),
+        }
+    }
+}
+
+impl JsonEnvelope {
+    /// Writes the envelope to the common output, pretty printed according to its style.
+    pub fn write(&self, io: &mut impl CurlautOutput) -> std::io::Result<()> {
+        let json = serde_json::to_value(self).map_err(std::io::Error::other)?;
+        let style = io.common_style();
+        pretty::write_json(io.common(), style, &json)?;
+        writeln!(io.common())
+    }
+}
+
+/// Repeated headers are combined into one value separated by comma.
+fn headers_to_json(headers: &HeaderMap) -> Map<String, Value> {
+    let mut json = Map::new();
+    for name in headers.keys() {
+        let values: Vec<_> = headers
+            .get_all(name)
+            .iter()
+            .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned())
+            .collect();
+        json.insert(name.to_string(), Value::from(values.join(", ")));
+    }
+    json
+}
@@ -0,0 +1,249 @@
+use crate::request::timings::HttpTimings;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use reqwest::blocking::Request;
+use reqwest::header::{HeaderMap, AUTHORIZATION, CONTENT_TYPE};
+use reqwest::{StatusCode, Version};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use url::form_urlencoded;
+
+const REDACTED: &str = "<redacted>";
+
+/// Form parameters and top level JSON fields never written to HAR files.
+const SECRET_FIELDS: [&str; 5] = [
+    "password",
+    "client_secret",
+    "access_token",
+    "refresh_token",
+    "id_token",
+];
+
+/// Appends recorded exchanges to HAR 1.2 file, creates the file if it doesn't exist.
+pub struct HarRecorder {
+    path: PathBuf,
+}
+
+impl HarRecorder {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// The file is rewritten after every exchange, so it's complete even if a later one fails.
+    pub fn record(&mut self, entry: HarEntry) -> std::io::Result<()> {
+        let har_error = |err: &dyn std::fmt::Display| {
+            std::io::Error::other(format!(
+                "Can't write HAR file '{}': {err}",
+                self.path.display()
+            ))
+        };
+        let mut archive = match fs::read(&self.path) {
+            Ok(content) => serde_json::from_slice(&content).map_err(|err| har_error(&err))?,
+            Err(err) if err.kind() == ErrorKind::NotFound => HarArchive::new(),
+            Err(err) => return Err(har_error(&err)),
+        };
+        archive.log.entries.push(entry);
+        let content = serde_json::to_vec_pretty(&archive).map_err(|err| har_error(&err))?;
+        fs::write(&self.path, content).map_err(|err| har_error(&err))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct HarArchive {
+    log: HarLog,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct HarLog {
+    version: String,
+    creator: Value,
+    entries: Vec<HarEntry>,
+}
+
+impl HarArchive {
+    fn new() -> Self {
+        Self {
+            log: HarLog {
+                version: "1.2".to_owned(),
+                creator: serde_json::json!({
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                }),
+                entries: Vec::new(),
+            },
+        }
+    }
+}
+
+/// Entry of HAR log, kept as JSON to preserve entries written by other tools.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct HarEntry(Value);
+
+impl HarEntry {
+    pub fn new(
+        started: SystemTime,
+        request: HarRequest,
+        response: HarResponse,
+        timings: &HttpTimings,
+    ) -> Self {
+        let millis = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        let start_transfer = timings.start_transfer.unwrap_or(timings.total);
+        let connect = timings.connect.unwrap_or_default();
+        Self(serde_json::json!({
+            "startedDateTime": humantime::format_rfc3339_millis(started).to_string(),
+            "time": millis(timings.total),
+            "request": request.0,
+            "response": response.0,
+            "cache": {},
+            "timings": {
+                "connect": timings.connect.map_or(-1.0, millis),
+                "send": 0,
+                "wait": millis(start_transfer.saturating_sub(connect)),
+                "receive": millis(timings.total.saturating_sub(start_transfer)),
+            },
+        }))
+    }
+}
+
+pub struct HarRequest(Value);
+
+impl HarRequest {
+    /// Must be created before the request is sent, secrets are redacted.
+    pub fn new(request: &Request) -> Self {
+        let url = request.url();
+        let query: Vec<_> = url
+            .query_pairs()
+            .map(|(name, value)| name_value(&name, &value))
+            .collect();
+        let body = request.body().and_then(|body| body.as_bytes());
+        let mut har_request = serde_json::json!({
+            "method": request.method().as_str(),
+            "url": url.as_str(),
+            "httpVersion": http_version(request.version()),
+            "cookies": [],
+            "headers": har_headers(request.headers()),
+            "queryString": query,
+            "headersSize": -1,
+            "bodySize": body.map_or(0, |body| body.len()),
+        });
+        if let Some(body) = body {
+            har_request["postData"] = post_data(request.headers(), body);
+        }
+        Self(har_request)
+    }
+}
+
+pub struct HarResponse(Value);
+
+impl HarResponse {
+    /// `body` is `None` if it wasn't kept in memory, e.g. saved to file.
+    /// `size` is of the decoded body, `body_size` is of the body as received.
+    pub fn new(
+        status: StatusCode,
+        version: Version,
+        headers: &HeaderMap,
+        body: Option<&[u8]>,
+        size: u64,
+        body_size: u64,
+    ) -> Self {
+        let mime_type = header_str(headers, CONTENT_TYPE);
+        let mut content = serde_json::json!({"size": size, "mimeType": mime_type});
+        if let Some(body) = body {
+            match redact_json(body).or_else(|| String::from_utf8(body.to_vec()).ok()) {
+                Some(text) => content["text"] = Value::from(text),
+                None => {
+                    content["text"] = Value::from(BASE64.encode(body));
+                    content["encoding"] = Value::from("base64");
+                }
+            }
+        }
+        Self(serde_json::json!({
+            "status": status.as_u16(),
+            "statusText": status.canonical_reason().unwrap_or_default(),
+            "httpVersion": http_version(version),
+            "cookies": [],
+            "headers": har_headers(headers),
+            "content": content,
+            "redirectURL": header_str(headers, reqwest::header::LOCATION),
+            "headersSize": -1,
+            "bodySize": body_size,
+        }))
+    }
+}
+
+fn http_version(version: Version) -> String {
+    format!("{version:?}")
+}
+
+fn header_str(headers: &HeaderMap, name: reqwest::header::HeaderName) -> &str {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+}
+
+fn har_headers(headers: &HeaderMap) -> Vec<Value> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if name == AUTHORIZATION {
+                REDACTED.into()
+            } else {
+                String::from_utf8_lossy(value.as_bytes())
+            };
+            name_value(name.as_str(), &value)
+        })
+        .collect()
+}
+
+fn name_value(name: &str, value: &str) -> Value {
+    serde_json::json!({"name": name, "value": value})
+}
+
+fn is_secret(name: &str) -> bool {
+    SECRET_FIELDS.contains(&name)
+}
+
+fn post_data(headers: &HeaderMap, body: &[u8]) -> Value {
+    let mime_type = header_str(headers, CONTENT_TYPE);
+    if mime_type.starts_with("application/x-www-form-urlencoded") {
+        let params: Vec<_> = form_urlencoded::parse(body)
+            .map(|(name, value)| {
+                let value = if is_secret(&name) {
+                    REDACTED.into()
+                } else {
+                    value
+                };
+                (name, value)
+            })
+            .collect();
+        let text = form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(&params)
+            .finish();
+        let params: Vec<_> = params
+            .iter()
+            .map(|(name, value)| name_value(name, value))
+            .collect();
+        return serde_json::json!({"mimeType": mime_type, "params": params, "text": text});
+    }
+    let text = redact_json(body).unwrap_or_else(|| String::from_utf8_lossy(body).into_owned());
+    serde_json::json!({"mimeType": mime_type, "text": text})
+}
+
+/// `None` if the body is not a JSON object.
+fn redact_json(body: &[u8]) -> Option<String> {
+    let Ok(Value::Object(mut json)) = serde_json::from_slice(body) else {
+        return None;
+    };
+    for (name, value) in json.iter_mut() {
+        if is_secret(name) {
+            *value = Value::from(REDACTED);
+        }
+    }
+    Some(Value::Object(json).to_string())
+}
@@ -1,11 +1,40 @@
+use crate::output::pretty::{OutputStyle, PrettyMode};
+use std::fs::File;
 use std::io::Write;
 
+pub mod envelope;
+pub mod har;
+pub mod pretty;
+pub mod select;
 pub mod stdio;
+pub mod write_out;
 
 pub trait CurlautOutput {
     fn enable_verbose(&mut self);
 
+    /// Overrides the style chosen by default, e.g. depending on whether output is a terminal.
+    fn set_pretty(&mut self, mode: PrettyMode);
+
+    fn common_style(&self) -> OutputStyle;
+
+    fn verbose_style(&self) -> OutputStyle;
+
+    /// Makes `head()` write to the common output, before the body.
+    fn include_head(&mut self);
+
+    /// Makes `head()` write to the file as well.
+    fn dump_head(&mut self, file: File);
+
     fn common(&mut self) -> &mut impl Write;
 
     fn verbose(&mut self) -> &mut impl Write;
+
+    /// Response status line and headers, discarded unless included or dumped.
+    fn head(&mut self) -> &mut impl Write;
+
+    /// Summaries asked for explicitly, like `--timing`, shown regardless of verbosity.
+    fn summary(&mut self) -> &mut impl Write;
+
+    /// Progress of long operations, may be overwritten using `\r`.
+    fn progress(&mut self) -> &mut impl Write;
 }
@@ -0,0 +1,311 @@
+use encoding_rs::{Encoding, UTF_8};
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use serde::Serialize;
+use serde_json::ser::{CompactFormatter, Formatter, PrettyFormatter};
+use serde_json::{Serializer, Value};
+use std::borrow::Cow;
+use std::io::Write;
+
+/// `--pretty` option: what to do with JSON body and verbose headers.
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum PrettyMode {
+    /// format and colorize
+    All,
+    /// only format: indent JSON, print headers unquoted
+    Format,
+    /// only colorize
+    Colors,
+    /// print as is
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutputStyle {
+    pub format: bool,
+    pub colors: bool,
+}
+
+impl OutputStyle {
+    pub const RAW: OutputStyle = OutputStyle {
+        format: false,
+        colors: false,
+    };
+
+    pub fn from_mode(mode: PrettyMode) -> OutputStyle {
+        OutputStyle {
+            format: matches!(mode, PrettyMode::All | PrettyMode::Format),
+            colors: matches!(mode, PrettyMode::All | PrettyMode::Colors),
+        }
+    }
+}
+
+const KEY_COLOR: &str = "34;1";
+const STRING_COLOR: &str = "32";
+const NUMBER_COLOR: &str = "33";
+const LITERAL_COLOR: &str = "35";
+const NULL_COLOR: &str = "90";
+const HEADER_NAME_COLOR: &str = "36";
+const STATUS_OK_COLOR: &str = "32";
+const STATUS_REDIRECT_COLOR: &str = "33";
+const STATUS_ERROR_COLOR: &str = "31";
+
+/// Whether the content type is `application/json`, `text/json` or `*/*+json`.
+/// Text in `charset` of the content type, UTF-8 if it's missing or unknown, like reqwest does.
+/// Invalid sequences are replaced.
+pub fn decode_text<'a>(body: &'a [u8], content_type: Option<&str>) -> Cow<'a, str> {
+    let charset = content_type
+        .into_iter()
+        .flat_map(|content_type| content_type.split(';').skip(1))
+        .filter_map(|param| param.split_once('='))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("charset"))
+        .map(|(_, value)| value.trim().trim_matches('"'));
+    let encoding = charset
+        .and_then(|charset| Encoding::for_label(charset.as_bytes()))
+        .unwrap_or(UTF_8);
+    encoding.decode(body).0
+}
+
+pub fn is_json_content_type(content_type: &str) -> bool {
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    match essence.split_once('/') {
+        Some((_, subtype)) => subtype == "json" || subtype.ends_with("+json"),
+        None => false,
+    }
+}
+
+/// Writes body formatted and/or colorized if it's JSON, otherwise bytes as received.
+pub fn write_body(
+    w: &mut impl Write,
+    style: OutputStyle,
+    content_type: Option<&str>,
+    body: &[u8],
+) -> std::io::Result<()> {
+    let is_json = content_type.is_some_and(is_json_content_type);
+    if style == OutputStyle::RAW || !is_json {
+        return w.write_all(body);
+    }
+    let Ok(json) = serde_json::from_str::<Value>(&decode_text(body, content_type)) else {
+        return w.write_all(body);
+    };
+    write_json(w, style, &json)?;
+    if style.format {
+        writeln!(w)?;
+    }
+    Ok(())
+}
+
+pub fn write_json(w: &mut impl Write, style: OutputStyle, json: &Value) -> std::io::Result<()> {
+    match (style.format, style.colors) {
+        (true, true) => serialize(w, json, ColorFormatter::new(PrettyFormatter::new())),
+        (true, false) => serialize(w, json, PrettyFormatter::new()),
+        (false, true) => serialize(w, json, ColorFormatter::new(CompactFormatter)),
+        (false, false) => serialize(w, json, CompactFormatter),
+    }
+}
+
+/// Writes every selected value on its own line, strings unquoted if `raw_strings`.
+pub fn write_selected(
+    w: &mut impl Write,
+    style: OutputStyle,
+    values: &[Value],
+    raw_strings: bool,
+) -> std::io::Result<()> {
+    for value in values {
+        match value {
+            Value::String(s) if raw_strings => write!(w, "{s}")?,
+            value => write_json(w, style, value)?,
+        }
+        writeln!(w)?;
+    }
+    Ok(())
+}
+
+fn serialize(w: &mut impl Write, json: &Value, formatter: impl Formatter) -> std::io::Result<()> {
+    let mut serializer = Serializer::with_formatter(w, formatter);
+    json.serialize(&mut serializer)
+        .map_err(std::io::Error::other)
+}
+
+/// Writes verbose header line like `< "name": "value"`, unquoted if formatted.
+pub fn write_header(
+    w: &mut impl Write,
+    style: OutputStyle,
+    direction: char,
+    name: &str,
+    value: &[u8],
+) -> std::io::Result<()> {
+    let value = String::from_utf8_lossy(value);
+    let (name, value) = if style.format {
+        (name.to_owned(), value.into_owned())
+    } else {
+        (format!("{name:?}"), format!("{value:?}"))
+    };
+    write!(w, "{direction} ")?;
+    write_colored(w, style, HEADER_NAME_COLOR, &name)?;
+    writeln!(w, ": {value}")
+}
+
+/// Writes verbose status line like `< HTTP/1.1 200 OK`, colored by status class.
+pub fn write_status_line(
+    w: &mut impl Write,
+    style: OutputStyle,
+    version: &str,
+    status: StatusCode,
+) -> std::io::Result<()> {
+    let color = if status.is_client_error() || status.is_server_error() {
+        STATUS_ERROR_COLOR
+    } else if status.is_redirection() {
+        STATUS_REDIRECT_COLOR
+    } else {
+        STATUS_OK_COLOR
+    };
+    write!(w, "< {version} ")?;
+    write_colored(w, style, color, &status.to_string())?;
+    writeln!(w)
+}
+
+/// Writes response head as received, like `HTTP/1.1 200 OK` and `name: value` lines
+/// followed by an empty line, for `--include` and `--dump-header`.
+pub fn write_head(
+    w: &mut impl Write,
+    version: &str,
+    status: StatusCode,
+    headers: &HeaderMap,
+) -> std::io::Result<()> {
+    write!(w, "{version} {status}\r\n")?;
+    for (name, value) in headers {
+        write!(w, "{name}: ")?;
+        w.write_all(value.as_bytes())?;
+        write!(w, "\r\n")?;
+    }
+    write!(w, "\r\n")?;
+    w.flush()
+}
+
+fn write_colored(
+    w: &mut impl Write,
+    style: OutputStyle,
+    color: &str,
+    text: &str,
+) -> std::io::Result<()> {
+    if style.colors {
+        write!(w, "\x1b[{color}m{text}\x1b[0m")
+    } else {
+        write!(w, "{text}")
+    }
+}
+
+/// Adds ANSI colors to JSON tokens, layout is up to the inner formatter.
+struct ColorFormatter<F> {
+    inner: F,
+    in_key: bool,
+}
+
+impl<F: Formatter> ColorFormatter<F> {
+    fn new(inner: F) -> Self {
+        Self {
+            inner,
+            in_key: false,
+        }
+    }
+
+    fn colored<W: ?Sized + Write>(
+        &mut self,
+        w: &mut W,
+        color: &str,
+        write: impl FnOnce(&mut F, &mut W) -> std::io::Result<()>,
+    ) -> std::io::Result<()> {
+        write!(w, "\x1b[{color}m")?;
+        write(&mut self.inner, w)?;
+        write!(w, "\x1b[0m")
+    }
+}
+
+impl<F: Formatter> Formatter for ColorFormatter<F> {
+    fn write_null<W: ?Sized + Write>(&mut self, w: &mut W) -> std::io::Result<()> {
+        self.colored(w, NULL_COLOR, |f, w| f.write_null(w))
+    }
+
+    fn write_bool<W: ?Sized + Write>(&mut self, w: &mut W, value: bool) -> std::io::Result<()> {
+        self.colored(w, LITERAL_COLOR, |f, w| f.write_bool(w, value))
+    }
+
+    fn write_i64<W: ?Sized + Write>(&mut self, w: &mut W, value: i64) -> std::io::Result<()> {
+        self.colored(w, NUMBER_COLOR, |f, w| f.write_i64(w, value))
+    }
+
+    fn write_u64<W: ?Sized + Write>(&mut self, w: &mut W, value: u64) -> std::io::Result<()> {
+        self.colored(w, NUMBER_COLOR, |f, w| f.write_u64(w, value))
+    }
+
+    fn write_f64<W: ?Sized + Write>(&mut self, w: &mut W, value: f64) -> std::io::Result<()> {
+        self.colored(w, NUMBER_COLOR, |f, w| f.write_f64(w, value))
+    }
+
+    fn begin_string<W: ?Sized + Write>(&mut self, w: &mut W) -> std::io::Result<()> {
+        let color = if self.in_key { KEY_COLOR } else { STRING_COLOR };
+        write!(w, "\x1b[{color}m")?;
+        self.inner.begin_string(w)
+    }
+
+    fn end_string<W: ?Sized + Write>(&mut self, w: &mut W) -> std::io::Result<()> {
+        self.inner.end_string(w)?;
+        write!(w, "\x1b[0m")
+    }
+
+    fn begin_array<W: ?Sized + Write>(&mut self, w: &mut W) -> std::io::Result<()> {
+        self.inner.begin_array(w)
+    }
+
+    fn end_array<W: ?Sized + Write>(&mut self, w: &mut W) -> std::io::Result<()> {
+        self.inner.end_array(w)
+    }
+
+    fn begin_array_value<W: ?Sized + Write>(
+        &mut self,
+        w: &mut W,
+        first: bool,
+    ) -> std::io::Result<()> {
+        self.inner.begin_array_value(w, first)
+    }
+
+    fn end_array_value<W: ?Sized + Write>(&mut self, w: &mut W) -> std::io::Result<()> {
+        self.inner.end_array_value(w)
+    }
+
+    fn begin_object<W: ?Sized + Write>(&mut self, w: &mut W) -> std::io::Result<()> {
+        self.inner.begin_object(w)
+    }
+
+    fn end_object<W: ?Sized + Write>(&mut self, w: &mut W) -> std::io::Result<()> {
+        self.inner.end_object(w)
+    }
+
+    fn begin_object_key<W: ?Sized + Write>(
+        &mut self,
+        w: &mut W,
+        first: bool,
+    ) -> std::io::Result<()> {
+        self.in_key = true;
+        self.inner.begin_object_key(w, first)
+    }
+
+    fn end_object_key<W: ?Sized + Write>(&mut self, w: &mut W) -> std::io::Result<()> {
+        self.in_key = false;
+        self.inner.end_object_key(w)
+    }
+
+    fn begin_object_value<W: ?Sized + Write>(&mut self, w: &mut W) -> std::io::Result<()> {
+        self.inner.begin_object_value(w)
+    }
+
+    fn end_object_value<W: ?Sized + Write>(&mut self, w: &mut W) -> std::io::Result<()> {
+        self.inner.end_object_value(w)
+    }
+}
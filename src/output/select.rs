@@ -0,0 +1,285 @@
+use serde_json::Value;
+use std::fmt::{Display, Formatter};
+
+/// Failure to parse or apply `--select` expression.
+#[derive(Debug, thiserror::Error)]
+pub enum SelectError {
+    #[error("Invalid select expression '{expr}': {reason}")]
+    Parse { expr: String, reason: String },
+    #[error("Response body is not JSON, can't select '{0}'")]
+    NotJson(String),
+    #[error("Can't apply '{step}' to {value_type}")]
+    Apply {
+        step: String,
+        value_type: &'static str,
+    },
+}
+
+/// Subset of jq filters: `.a.b`, `."a b"`, `.["a"]`, `[0]`, `[-1]`, `[1:3]`, `[]`, `[*]`, `.*`,
+/// `| length`, `| keys`. JSONPath-like `$.a[*].b` is accepted as well.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonSelector {
+    expr: String,
+    pipeline: Vec<Vec<Step>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Field(String),
+    Index(i64),
+    Slice(Option<i64>, Option<i64>),
+    Iterate,
+    Length,
+    Keys,
+}
+
+impl Display for Step {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Step::Field(name) => write!(f, ".{name}"),
+            Step::Index(index) => write!(f, "[{index}]"),
+            Step::Slice(from, to) => {
+                let bound = |b: &Option<i64>| b.map(|b| b.to_string()).unwrap_or_default();
+                write!(f, "[{}:{}]", bound(from), bound(to))
+            }
+            Step::Iterate => write!(f, "[]"),
+            Step::Length => write!(f, "length"),
+            Step::Keys => write!(f, "keys"),
+        }
+    }
+}
+
+impl JsonSelector {
+    pub fn parse(expr: &str) -> Result<JsonSelector, SelectError> {
+        let pipeline = split_pipeline(expr)
+            .into_iter()
+            .map(|filter| Parser::new(expr, filter.trim()).parse())
+            .collect::<Result<_, _>>()?;
+        Ok(JsonSelector {
+            expr: expr.to_owned(),
+            pipeline,
+        })
+    }
+
+    pub fn apply_to_str(&self, body: &str) -> Result<Vec<Value>, SelectError> {
+        let json =
+            serde_json::from_str(body).map_err(|_| SelectError::NotJson(self.expr.clone()))?;
+        self.apply(json)
+    }
+
+    pub fn apply(&self, json: Value) -> Result<Vec<Value>, SelectError> {
+        let mut values = vec![json];
+        for steps in &self.pipeline {
+            for step in steps {
+                values = values.into_iter().try_fold(Vec::new(), |mut acc, value| {
+                    acc.extend(apply_step(step, value)?);
+                    Ok::<_, SelectError>(acc)
+                })?;
+            }
+        }
+        Ok(values)
+    }
+}
+
+fn apply_step(step: &Step, value: Value) -> Result<Vec<Value>, SelectError> {
+    let cant_apply = |value: &Value| SelectError::Apply {
+        step: step.to_string(),
+        value_type: type_name(value),
+    };
+    let selected = match (step, value) {
+        (Step::Field(_) | Step::Index(_) | Step::Slice(..), Value::Null) => vec![Value::Null],
+        (Step::Field(name), Value::Object(mut map)) => {
+            vec![map.remove(name).unwrap_or(Value::Null)]
+        }
+        (Step::Index(index), Value::Array(mut array)) => {
+            let index = resolve_index(*index, array.len());
+            match index {
+                Some(index) if index < array.len() => vec![array.swap_remove(index)],
+                _ => vec![Value::Null],
+            }
+        }
+        (Step::Slice(from, to), Value::Array(array)) => {
+            let len = array.len();
+            let from = from.map_or(0, |from| resolve_index(from, len).unwrap_or(0).min(len));
+            let to = to.map_or(len, |to| resolve_index(to, len).unwrap_or(0).min(len));
+            let slice = array.into_iter().skip(from).take(to.saturating_sub(from));
+            vec![Value::Array(slice.collect())]
+        }
+        (Step::Iterate, Value::Array(array)) => array,
+        (Step::Iterate, Value::Object(map)) => map.into_iter().map(|(_, v)| v).collect(),
+        (Step::Length, Value::Null) => vec![Value::from(0)],
+        (Step::Length, Value::Array(array)) => vec![Value::from(array.len())],
+        (Step::Length, Value::Object(map)) => vec![Value::from(map.len())],
+        (Step::Length, Value::String(s)) => vec![Value::from(s.chars().count())],
+        (Step::Length, Value::Number(n)) => match n.as_i64() {
+            Some(n) => vec![Value::from(n.unsigned_abs())],
+            None => vec![Value::from(n.as_f64().unwrap_or_default().abs())],
+        },
+        (Step::Keys, Value::Object(map)) => {
+            let mut keys: Vec<String> = map.into_iter().map(|(k, _)| k).collect();
+            keys.sort();
+            vec![Value::from(keys)]
+        }
+        (Step::Keys, Value::Array(array)) => {
+            vec![Value::from((0..array.len()).collect::<Vec<_>>())]
+        }
+        (_, value) => return Err(cant_apply(&value)),
+    };
+    Ok(selected)
+}
+
+/// Splits by '|' outside of quoted keys and brackets, which may contain it.
+fn split_pipeline(expr: &str) -> Vec<&str> {
+    let mut filters = Vec::new();
+    let mut start = 0;
+    let mut in_quotes = false;
+    let mut in_brackets = false;
+    for (index, char) in expr.char_indices() {
+        match char {
+            '"' => in_quotes = !in_quotes,
+            '[' if !in_quotes => in_brackets = true,
+            ']' if !in_quotes => in_brackets = false,
+            '|' if !in_quotes && !in_brackets => {
+                filters.push(&expr[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    filters.push(&expr[start..]);
+    filters
+}
+
+/// Negative index counts from the end, `None` if it's before the start.
+fn resolve_index(index: i64, len: usize) -> Option<usize> {
+    if index >= 0 {
+        Some(index as usize)
+    } else {
+        len.checked_sub(index.unsigned_abs() as usize)
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+struct Parser<'a> {
+    expr: &'a str,
+    rest: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn new(expr: &'a str, filter: &'a str) -> Self {
+        Self { expr, rest: filter }
+    }
+
+    fn parse(mut self) -> Result<Vec<Step>, SelectError> {
+        match self.rest {
+            "length" => return Ok(vec![Step::Length]),
+            "keys" => return Ok(vec![Step::Keys]),
+            "" => return Err(self.error("empty filter")),
+            _ => {}
+        }
+        if let Some(rest) = self.rest.strip_prefix('$') {
+            self.rest = rest;
+        } else if !self.rest.starts_with('.') {
+            return Err(self.error("filter must start with '.'"));
+        }
+        let mut steps = Vec::new();
+        while !self.rest.is_empty() {
+            if let Some(rest) = self.rest.strip_prefix('.') {
+                self.rest = rest;
+                if let Some(step) = self.parse_field()? {
+                    steps.push(step);
+                }
+            } else if self.rest.starts_with('[') {
+                steps.push(self.parse_brackets()?);
+            } else {
+                return Err(self.error(&format!("unexpected '{}'", self.rest)));
+            }
+        }
+        Ok(steps)
+    }
+
+    /// After '.', returns `None` for identity `.` or `.[...]`.
+    fn parse_field(&mut self) -> Result<Option<Step>, SelectError> {
+        if self.rest.is_empty() || self.rest.starts_with('[') {
+            return Ok(None);
+        }
+        if let Some(rest) = self.rest.strip_prefix('*') {
+            self.rest = rest;
+            return Ok(Some(Step::Iterate));
+        }
+        if self.rest.starts_with('"') {
+            return self.parse_quoted().map(|name| Some(Step::Field(name)));
+        }
+        let end = self
+            .rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+            .unwrap_or(self.rest.len());
+        if end == 0 {
+            return Err(self.error(&format!("unexpected '{}'", self.rest)));
+        }
+        let (name, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        Ok(Some(Step::Field(name.to_owned())))
+    }
+
+    fn parse_quoted(&mut self) -> Result<String, SelectError> {
+        let end = self.rest[1..]
+            .find('"')
+            .ok_or_else(|| self.error("unterminated string"))?;
+        let name = self.rest[1..=end].to_owned();
+        self.rest = &self.rest[end + 2..];
+        Ok(name)
+    }
+
+    fn parse_brackets(&mut self) -> Result<Step, SelectError> {
+        let inner_rest = &self.rest[1..];
+        if inner_rest.starts_with('"') {
+            self.rest = inner_rest;
+            let name = self.parse_quoted()?;
+            self.rest = self
+                .rest
+                .strip_prefix(']')
+                .ok_or_else(|| self.error("expected ']'"))?;
+            return Ok(Step::Field(name));
+        }
+        let (inner, rest) = inner_rest
+            .split_once(']')
+            .ok_or_else(|| self.error("expected ']'"))?;
+        self.rest = rest;
+        let inner = inner.trim();
+        if inner.is_empty() || inner == "*" {
+            return Ok(Step::Iterate);
+        }
+        let parse_bound = |bound: &str| -> Result<Option<i64>, SelectError> {
+            let bound = bound.trim();
+            if bound.is_empty() {
+                return Ok(None);
+            }
+            bound
+                .parse()
+                .map(Some)
+                .map_err(|_| self.error(&format!("invalid index '{bound}'")))
+        };
+        match inner.split_once(':') {
+            Some((from, to)) => Ok(Step::Slice(parse_bound(from)?, parse_bound(to)?)),
+            None => Ok(Step::Index(parse_bound(inner)?.unwrap_or_default())),
+        }
+    }
+
+    fn error(&self, reason: &str) -> SelectError {
+        SelectError::Parse {
+            expr: self.expr.to_owned(),
+            reason: reason.to_owned(),
+        }
+    }
+}
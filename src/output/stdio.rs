@@ -1,8 +1,15 @@
-use std::io::{stderr, stdout, Write};
+use crate::output::pretty::{OutputStyle, PrettyMode};
+use std::fs::File;
+use std::io::{stderr, stdout, IsTerminal, Write};
 
 pub struct CurlautStdOutput {
     out: CurlautStdOut,
     err: CurlautStdErr,
+    progress: CurlautStdErr,
+    summary: CurlautStdErr,
+    head: CurlautStdHead,
+    out_style: OutputStyle,
+    err_style: OutputStyle,
 }
 
 impl CurlautStdOutput {
@@ -10,8 +17,34 @@ impl CurlautStdOutput {
         Self {
             out: CurlautStdOut {},
             err: CurlautStdErr { enabled: false },
+            // progress is useless in logs
+            progress: CurlautStdErr {
+                enabled: stderr().is_terminal(),
+            },
+            summary: CurlautStdErr { enabled: true },
+            head: CurlautStdHead {
+                include: false,
+                dump: None,
+            },
+            // keep output raw when piped
+            out_style: Self::style_for_terminal(stdout().is_terminal()),
+            err_style: Self::style_for_terminal(stderr().is_terminal()),
         }
     }
+
+    fn style_for_terminal(is_terminal: bool) -> OutputStyle {
+        if is_terminal {
+            OutputStyle::from_mode(PrettyMode::All)
+        } else {
+            OutputStyle::RAW
+        }
+    }
+}
+
+impl Default for CurlautStdOutput {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl crate::output::CurlautOutput for CurlautStdOutput {
@@ -19,6 +52,27 @@ impl crate::output::CurlautOutput for CurlautStdOutput {
         self.err.enabled = true;
     }
 
+    fn set_pretty(&mut self, mode: PrettyMode) {
+        self.out_style = OutputStyle::from_mode(mode);
+        self.err_style = OutputStyle::from_mode(mode);
+    }
+
+    fn common_style(&self) -> OutputStyle {
+        self.out_style
+    }
+
+    fn verbose_style(&self) -> OutputStyle {
+        self.err_style
+    }
+
+    fn include_head(&mut self) {
+        self.head.include = true;
+    }
+
+    fn dump_head(&mut self, file: File) {
+        self.head.dump = Some(file);
+    }
+
     fn common(&mut self) -> &mut impl Write {
         &mut self.out
     }
@@ -26,6 +80,18 @@ impl crate::output::CurlautOutput for CurlautStdOutput {
     fn verbose(&mut self) -> &mut impl Write {
         &mut self.err
     }
+
+    fn head(&mut self) -> &mut impl Write {
+        &mut self.head
+    }
+
+    fn summary(&mut self) -> &mut impl Write {
+        &mut self.summary
+    }
+
+    fn progress(&mut self) -> &mut impl Write {
+        &mut self.progress
+    }
 }
 
 struct CurlautStdOut {}
@@ -34,6 +100,11 @@ struct CurlautStdErr {
     enabled: bool,
 }
 
+struct CurlautStdHead {
+    include: bool,
+    dump: Option<File>,
+}
+
 impl Write for CurlautStdOut {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
         stdout().write(buf)
@@ -59,3 +130,25 @@ impl Write for CurlautStdErr {
         Ok(())
     }
 }
+
+impl Write for CurlautStdHead {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.include {
+            stdout().write_all(buf)?;
+        }
+        if let Some(file) = &mut self.dump {
+            file.write_all(buf)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if self.include {
+            stdout().flush()?;
+        }
+        if let Some(file) = &mut self.dump {
+            file.flush()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,157 @@
+use crate::request::timings::HttpTimings;
+use reqwest::StatusCode;
+use std::io::Write;
+use std::time::Duration;
+use url::Url;
+
+/// Failure to parse `--write-out` template.
+#[derive(Debug, thiserror::Error)]
+pub enum WriteOutError {
+    #[error("Unknown write-out variable '%{{{0}}}'")]
+    UnknownVariable(String),
+    #[error("Unterminated write-out variable in '{0}'")]
+    Unterminated(String),
+}
+
+/// curl-like `--write-out` template: text with `%{variable}`, `%%`, `\n`, `\r` and `\t`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WriteOut {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Text(String),
+    Variable(Variable),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Variable {
+    HttpCode,
+    ContentType,
+    SizeDownload,
+    TimeAuth,
+    TimeConnect,
+    TimeStartTransfer,
+    TimeTotal,
+    UrlEffective,
+}
+
+impl Variable {
+    fn parse(name: &str) -> Result<Variable, WriteOutError> {
+        let variable = match name {
+            "http_code" | "response_code" => Variable::HttpCode,
+            "content_type" => Variable::ContentType,
+            "size_download" => Variable::SizeDownload,
+            "time_auth" => Variable::TimeAuth,
+            "time_connect" => Variable::TimeConnect,
+            "time_starttransfer" => Variable::TimeStartTransfer,
+            "time_total" => Variable::TimeTotal,
+            "url_effective" => Variable::UrlEffective,
+            name => return Err(WriteOutError::UnknownVariable(name.to_owned())),
+        };
+        Ok(variable)
+    }
+}
+
+/// What is known about the finished exchange.
+pub struct ExchangeSummary<'a> {
+    pub url: &'a Url,
+    pub status: StatusCode,
+    pub content_type: Option<&'a str>,
+    /// body bytes received, before decoding like curl counts them, 0 if the body was skipped
+    pub size_download: u64,
+    pub timings: HttpTimings,
+}
+
+impl WriteOut {
+    pub fn parse(template: &str) -> Result<WriteOut, WriteOutError> {
+        let mut segments = Vec::new();
+        let mut text = String::new();
+        let mut rest = template;
+        while let Some(c) = rest.chars().next() {
+            match (c, rest[c.len_utf8()..].chars().next()) {
+                ('%', Some('%')) => text.push('%'),
+                ('%', Some('{')) => {
+                    let end = rest
+                        .find('}')
+                        .ok_or_else(|| WriteOutError::Unterminated(template.to_owned()))?;
+                    let variable = Variable::parse(&rest[2..end])?;
+                    if !text.is_empty() {
+                        segments.push(Segment::Text(std::mem::take(&mut text)));
+                    }
+                    segments.push(Segment::Variable(variable));
+                    rest = &rest[end + 1..];
+                    continue;
+                }
+                ('\\', Some('n')) => text.push('\n'),
+                ('\\', Some('r')) => text.push('\r'),
+                ('\\', Some('t')) => text.push('\t'),
+                ('\\', Some('\\')) => text.push('\\'),
+                (c, _) => {
+                    text.push(c);
+                    rest = &rest[c.len_utf8()..];
+                    continue;
+                }
+            }
+            // two-char sequence was consumed
+            rest = &rest[2..];
+        }
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+        Ok(WriteOut { segments })
+    }
+
+    pub fn write(&self, w: &mut impl Write, summary: &ExchangeSummary) -> std::io::Result<()> {
+        for segment in &self.segments {
+            match segment {
+                Segment::Text(text) => write!(w, "{text}")?,
+                Segment::Variable(variable) => write_variable(w, *variable, summary)?,
+            }
+        }
+        w.flush()
+    }
+}
+
+fn write_variable(
+    w: &mut impl Write,
+    variable: Variable,
+    summary: &ExchangeSummary,
+) -> std::io::Result<()> {
+    let timings = &summary.timings;
+    match variable {
+        Variable::HttpCode => write!(w, "{:03}", summary.status.as_u16()),
+        Variable::ContentType => write!(w, "{}", summary.content_type.unwrap_or_default()),
+        Variable::SizeDownload => write!(w, "{}", summary.size_download),
+        Variable::TimeAuth => write_seconds(w, timings.auth),
+        Variable::TimeConnect => write_seconds(w, timings.connect),
+        Variable::TimeStartTransfer => write_seconds(w, timings.start_transfer),
+        Variable::TimeTotal => write_seconds(w, Some(timings.total)),
+        Variable::UrlEffective => write!(w, "{}", summary.url),
+    }
+}
+
+/// Seconds with microseconds like curl does, phases which didn't happen are zero.
+fn write_seconds(w: &mut impl Write, duration: Option<Duration>) -> std::io::Result<()> {
+    write!(w, "{:.6}", duration.unwrap_or_default().as_secs_f64())
+}
+
+/// `--timing` summary, one phase per line.
+pub fn write_timing_table(w: &mut impl Write, timings: &HttpTimings) -> std::io::Result<()> {
+    let rows = [
+        ("auth", timings.auth),
+        ("connect", timings.connect),
+        ("start transfer", timings.start_transfer),
+        ("total", Some(timings.total)),
+    ];
+    for (name, duration) in rows {
+        match duration {
+            Some(duration) => {
+                writeln!(w, "{name:<16}{:>10.3} ms", duration.as_secs_f64() * 1000.0)?
+            }
+            None => writeln!(w, "{name:<16}{:>10} ms", "-")?,
+        }
+    }
+    w.flush()
+}
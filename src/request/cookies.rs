@@ -0,0 +1,328 @@
+use crate::request::request_error::RequestError;
+use reqwest::cookie::CookieStore;
+use reqwest::header::HeaderValue;
+use std::cmp::Reverse;
+use std::fs::{self, File};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use url::{Host, Url};
+
+const NETSCAPE_HEADER: &str = "# Netscape HTTP Cookie File\n";
+const HTTP_ONLY_PREFIX: &str = "#HttpOnly_";
+
+/// `-b`, `-c` and the persistent jar of the Keycloak config.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CookieOptions {
+    /// `name=value; name2=value2` sent to the target host, or a Netscape cookie file to read
+    pub cookies: Vec<String>,
+    /// Netscape cookie file all cookies are written to after the request
+    pub cookie_jar: Option<PathBuf>,
+    /// Netscape cookie file read before and written after the request
+    pub persistent_jar: Option<PathBuf>,
+}
+
+impl CookieOptions {
+    /// `None` if cookies are not used, the client then neither sends nor keeps them.
+    pub(crate) fn load(&self) -> Result<Option<Arc<CookieJar>>, RequestError> {
+        if self.cookies.is_empty() && self.cookie_jar.is_none() && self.persistent_jar.is_none() {
+            return Ok(None);
+        }
+        let mut jar = CookieJar::default();
+        if let Some(path) = &self.persistent_jar {
+            jar.read_file(path)?;
+        }
+        for cookie in &self.cookies {
+            if cookie.contains('=') {
+                jar.fixed.push(cookie.trim().to_owned());
+            } else {
+                jar.read_file(Path::new(cookie))?;
+            }
+        }
+        Ok(Some(Arc::new(jar)))
+    }
+
+    /// Writes the cookies to `-c` file and the persistent jar.
+    pub(crate) fn save(&self, jar: &CookieJar) -> Result<(), RequestError> {
+        for path in self.cookie_jar.iter().chain(&self.persistent_jar) {
+            jar.write_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Cookies kept during the run, shared with the http client which sends and stores them.
+#[derive(Debug, Default)]
+pub(crate) struct CookieJar {
+    cookies: Mutex<Vec<StoredCookie>>,
+    /// `-b name=value`, sent after the stored cookies until redirected to another host
+    fixed: Vec<String>,
+}
+
+impl CookieJar {
+    /// A missing file is an empty jar, lines which aren't cookies are skipped like curl does.
+    fn read_file(&mut self, path: &Path) -> Result<(), RequestError> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(source) => return Err(cookie_file_error(path, source)),
+        };
+        let now = now();
+        let cookies = self.cookies.get_mut().unwrap_or_else(|err| err.into_inner());
+        for cookie in content.lines().filter_map(StoredCookie::from_netscape) {
+            if !cookie.is_expired(now) {
+                cookies.retain(|stored| !stored.same_as(&cookie));
+                cookies.push(cookie);
+            }
+        }
+        Ok(())
+    }
+
+    fn write_file(&self, path: &Path) -> Result<(), RequestError> {
+        let now = now();
+        let mut content = NETSCAPE_HEADER.to_owned();
+        for cookie in self.lock().iter().filter(|cookie| !cookie.is_expired(now)) {
+            content.push_str(&cookie.to_netscape());
+            content.push('\n');
+        }
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|source| cookie_file_error(path, source))?;
+        }
+        create_private_file(path)
+            .and_then(|mut file| file.write_all(content.as_bytes()))
+            .map_err(|source| cookie_file_error(path, source))
+    }
+
+    /// `Cookie` header for the URL, longer paths first as RFC 6265 recommends.
+    /// `-b name=value` cookies follow the stored ones if `with_fixed`.
+    pub(crate) fn header(&self, url: &Url, with_fixed: bool) -> Option<HeaderValue> {
+        let now = now();
+        let cookies = self.lock();
+        let mut matching: Vec<&StoredCookie> = cookies
+            .iter()
+            .filter(|cookie| cookie.matches(url, now))
+            .collect();
+        matching.sort_by_key(|cookie| Reverse(cookie.path.len()));
+        let mut pairs: Vec<String> = matching
+            .iter()
+            .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+            .collect();
+        if with_fixed {
+            pairs.extend(self.fixed.iter().cloned());
+        }
+        if pairs.is_empty() {
+            return None;
+        }
+        HeaderValue::from_str(&pairs.join("; ")).ok()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<StoredCookie>> {
+        self.cookies.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl CookieStore for CookieJar {
+    fn set_cookies(&self, cookie_headers: &mut dyn Iterator<Item = &HeaderValue>, url: &Url) {
+        let now = now();
+        let mut cookies = self.lock();
+        for header in cookie_headers {
+            let Some(cookie) = StoredCookie::from_set_cookie(header, url, now) else {
+                continue;
+            };
+            cookies.retain(|stored| !stored.same_as(&cookie));
+            if !cookie.is_expired(now) {
+                cookies.push(cookie);
+            }
+        }
+    }
+
+    /// Only the stored cookies, the executor adds `-b` ones where they may go.
+    fn cookies(&self, url: &Url) -> Option<HeaderValue> {
+        self.header(url, false)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct StoredCookie {
+    /// lowercase, without the leading dot
+    domain: String,
+    /// sent to subdomains too, set by `Domain` attribute
+    include_subdomains: bool,
+    path: String,
+    secure: bool,
+    http_only: bool,
+    /// seconds since the epoch, 0 for a session cookie
+    expires: u64,
+    name: String,
+    value: String,
+}
+
+impl StoredCookie {
+    /// `None` if the header is invalid or its domain doesn't match the URL host or is too broad.
+    fn from_set_cookie(header: &HeaderValue, url: &Url, now: u64) -> Option<Self> {
+        let cookie = cookie::Cookie::parse(header.to_str().ok()?).ok()?;
+        let host = url.host_str()?.to_ascii_lowercase();
+        let (domain, include_subdomains) = match cookie.domain() {
+            Some(domain) if !domain.is_empty() => {
+                let domain = domain.trim_start_matches('.').to_ascii_lowercase();
+                if !domain_matches(&host, &domain) {
+                    return None;
+                }
+                // a single label like `com` is a public suffix and an IP address has no
+                // subdomains, RFC 6265 allows only the host itself to get such a cookie
+                let is_ip = !matches!(url.host(), Some(Host::Domain(_)));
+                if !is_ip && domain.contains('.') {
+                    (domain, true)
+                } else if domain == host {
+                    (domain, false)
+                } else {
+                    return None;
+                }
+            }
+            _ => (host, false),
+        };
+        let path = match cookie.path() {
+            Some(path) if path.starts_with('/') => path.to_owned(),
+            _ => default_path(url),
+        };
+        let expires = match (cookie.max_age(), cookie.expires_datetime()) {
+            // 1 is in the past, the cookie is removed
+            (Some(max_age), _) if max_age.whole_seconds() <= 0 => 1,
+            (Some(max_age), _) => now + max_age.whole_seconds() as u64,
+            (None, Some(date)) => date.unix_timestamp().max(1) as u64,
+            (None, None) => 0,
+        };
+        Some(Self {
+            domain,
+            include_subdomains,
+            path,
+            secure: cookie.secure().unwrap_or(false),
+            http_only: cookie.http_only().unwrap_or(false),
+            expires,
+            name: cookie.name().to_owned(),
+            value: cookie.value().to_owned(),
+        })
+    }
+
+    /// `domain  include_subdomains  path  secure  expires  name  value` separated by tabs,
+    /// `#HttpOnly_` before the domain marks HTTP-only cookies.
+    fn from_netscape(line: &str) -> Option<Self> {
+        let (line, http_only) = match line.strip_prefix(HTTP_ONLY_PREFIX) {
+            Some(line) => (line, true),
+            None => (line, false),
+        };
+        if line.starts_with('#') {
+            return None;
+        }
+        let line = line.trim_end_matches(['\r', '\n']);
+        let [domain, include_subdomains, path, secure, expires, name, value] =
+            line.split('\t').collect::<Vec<_>>()[..]
+        else {
+            return None;
+        };
+        Some(Self {
+            domain: domain.trim_start_matches('.').to_ascii_lowercase(),
+            include_subdomains: include_subdomains.eq_ignore_ascii_case("TRUE"),
+            path: path.to_owned(),
+            secure: secure.eq_ignore_ascii_case("TRUE"),
+            http_only,
+            expires: expires.parse().ok()?,
+            name: name.to_owned(),
+            value: value.to_owned(),
+        })
+    }
+
+    fn to_netscape(&self) -> String {
+        let flag = |value: bool| if value { "TRUE" } else { "FALSE" };
+        format!(
+            "{}{}{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            if self.http_only { HTTP_ONLY_PREFIX } else { "" },
+            if self.include_subdomains { "." } else { "" },
+            self.domain,
+            flag(self.include_subdomains),
+            self.path,
+            flag(self.secure),
+            self.expires,
+            self.name,
+            self.value
+        )
+    }
+
+    fn matches(&self, url: &Url, now: u64) -> bool {
+        let Some(host) = url.host_str() else {
+            return false;
+        };
+        let host = host.to_ascii_lowercase();
+        let domain_ok = if self.include_subdomains {
+            domain_matches(&host, &self.domain)
+        } else {
+            host == self.domain
+        };
+        let path = url.path();
+        let path_ok = path == self.path
+            || path.starts_with(&self.path)
+                && (self.path.ends_with('/') || path[self.path.len()..].starts_with('/'));
+        domain_ok && path_ok && (!self.secure || url.scheme() == "https") && !self.is_expired(now)
+    }
+
+    fn is_expired(&self, now: u64) -> bool {
+        self.expires != 0 && self.expires <= now
+    }
+
+    /// A new cookie with the same name, domain and path replaces the stored one.
+    fn same_as(&self, other: &StoredCookie) -> bool {
+        self.name == other.name && self.domain == other.domain && self.path == other.path
+    }
+}
+
+fn domain_matches(host: &str, domain: &str) -> bool {
+    host == domain
+        || host
+            .strip_suffix(domain)
+            .is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+/// Directory of the URL path, used when `Set-Cookie` has no `Path`.
+fn default_path(url: &Url) -> String {
+    match url.path().rfind('/') {
+        Some(0) | None => "/".to_owned(),
+        Some(index) => url.path()[..index].to_owned(),
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .unwrap_or_default()
+}
+
+/// Cookies are credentials, only the owner may read them.
+#[cfg(unix)]
+fn create_private_file(path: &Path) -> std::io::Result<File> {
+    use std::fs::{OpenOptions, Permissions};
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+    let file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    // the mode applies only to a new file
+    file.set_permissions(Permissions::from_mode(0o600))?;
+    Ok(file)
+}
+
+/// Permissions are inherited from the directory, the home directory is private by default.
+#[cfg(not(unix))]
+fn create_private_file(path: &Path) -> std::io::Result<File> {
+    File::create(path)
+}
+
+fn cookie_file_error(path: &Path, source: std::io::Error) -> RequestError {
+    RequestError::CookieFile {
+        path: path.to_owned(),
+        source,
+    }
+}
@@ -0,0 +1,95 @@
+use flate2::read::{MultiGzDecoder, ZlibDecoder};
+use reqwest::blocking::Response;
+use reqwest::header::CONTENT_ENCODING;
+use std::cell::Cell;
+use std::io::Read;
+use std::rc::Rc;
+
+/// `Accept-Encoding` sent with `--compressed`, everything `ResponseBody` can decode.
+pub const ACCEPT_ENCODING: &str = "gzip, deflate, br, zstd";
+
+const BROTLI_BUFFER_SIZE: usize = 4096;
+
+/// Response body as received or decoded according to `Content-Encoding`.
+/// Bodies in unknown encodings are left as is.
+pub(crate) struct ResponseBody {
+    reader: Box<dyn Read>,
+    received: Rc<Cell<u64>>,
+    /// applied encodings in the order of decoding, empty if not decoded
+    decoded: Vec<String>,
+}
+
+impl ResponseBody {
+    pub fn new(response: Response, decode: bool) -> std::io::Result<Self> {
+        let encodings: Vec<String> = response
+            .headers()
+            .get_all(CONTENT_ENCODING)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(|encoding| encoding.trim().to_ascii_lowercase())
+            .filter(|encoding| !encoding.is_empty() && encoding != "identity")
+            .rev()
+            .collect();
+        let received = Rc::new(Cell::new(0));
+        let mut reader: Box<dyn Read> = Box::new(CountingReader {
+            inner: response,
+            count: received.clone(),
+        });
+        let decodable = encodings.iter().all(|encoding| is_supported(encoding));
+        if !decode || !decodable {
+            return Ok(Self {
+                reader,
+                received,
+                decoded: Vec::new(),
+            });
+        }
+        for encoding in &encodings {
+            reader = match encoding.as_str() {
+                "gzip" | "x-gzip" => Box::new(MultiGzDecoder::new(reader)),
+                "deflate" => Box::new(ZlibDecoder::new(reader)),
+                "br" => Box::new(brotli::Decompressor::new(reader, BROTLI_BUFFER_SIZE)),
+                "zstd" => Box::new(zstd::stream::read::Decoder::new(reader)?),
+                _ => unreachable!("only supported encodings are decoded"),
+            };
+        }
+        Ok(Self {
+            reader,
+            received,
+            decoded: encodings,
+        })
+    }
+
+    /// Bytes read from the connection so far, before decoding.
+    pub fn received(&self) -> u64 {
+        self.received.get()
+    }
+
+    /// Encodings the body is decoded from, e.g. `gzip`, `None` if it's read as received.
+    pub fn decoded(&self) -> Option<String> {
+        (!self.decoded.is_empty()).then(|| self.decoded.join(", "))
+    }
+}
+
+impl Read for ResponseBody {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.reader.read(buf)
+    }
+}
+
+fn is_supported(encoding: &str) -> bool {
+    matches!(encoding, "gzip" | "x-gzip" | "deflate" | "br" | "zstd")
+}
+
+struct CountingReader<R> {
+    inner: R,
+    count: Rc<Cell<u64>>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.count.set(self.count.get() + read as u64);
+        Ok(read)
+    }
+}
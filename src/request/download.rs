@@ -0,0 +1,197 @@
+use crate::output::CurlautOutput;
+use crate::request::decoding::ResponseBody;
+use crate::request::request_error::RequestError;
+use crate::request::request_spec::{ContinueAt, DownloadFile, HttpDownload};
+use percent_encoding::percent_decode_str;
+use reqwest::header::{HeaderMap, CONTENT_DISPOSITION, CONTENT_LENGTH};
+use reqwest::StatusCode;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use url::Url;
+
+const BUFFER_SIZE: usize = 64 * 1024;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Offset to request with `Range` header, `None` if not resuming.
+pub fn resume_offset(download: &HttpDownload, url: &Url) -> Result<Option<u64>, RequestError> {
+    match download.continue_at {
+        None => Ok(None),
+        Some(ContinueAt::Offset(offset)) => Ok(Some(offset)),
+        Some(ContinueAt::FileSize) => {
+            // Content-Disposition is unknown yet, so the name is taken from URL
+            let path = local_path(download, url, None)?;
+            let size = path.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+            Ok(Some(size))
+        }
+    }
+}
+
+/// Streams response body to the file, appends to it if server returned the requested range.
+/// Returns the number of bytes written.
+pub(crate) fn save_body(
+    status: StatusCode,
+    headers: &HeaderMap,
+    body: &mut ResponseBody,
+    download: &HttpDownload,
+    url: &Url,
+    offset: Option<u64>,
+    io: &mut impl CurlautOutput,
+) -> Result<u64, RequestError> {
+    let partial = offset.is_some() && status == StatusCode::PARTIAL_CONTENT;
+    let path = local_path(download, url, offset.is_none().then_some(headers))?;
+    let file_error = |source| RequestError::File {
+        path: path.clone(),
+        source,
+    };
+    let file = if partial {
+        OpenOptions::new().create(true).append(true).open(&path)
+    } else {
+        if offset.is_some_and(|offset| offset > 0) {
+            writeln!(io.verbose(), "* Server ignored range, downloading whole file")?;
+        }
+        File::create(&path)
+    }
+    .map_err(file_error)?;
+    let already_downloaded = if partial { offset.unwrap_or(0) } else { 0 };
+    // length of decoded body is unknown
+    let total = headers
+        .get(CONTENT_LENGTH)
+        .filter(|_| body.decoded().is_none())
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok())
+        .map(|length| length + already_downloaded);
+    let mut progress = Progress::new(already_downloaded, total);
+    let written = copy_with_progress(body, file, url, &path, &mut progress, io)?;
+    writeln!(io.verbose(), "* Saved {written} bytes to {}", path.display())?;
+    Ok(written)
+}
+
+fn copy_with_progress(
+    body: &mut impl Read,
+    mut file: File,
+    url: &Url,
+    path: &Path,
+    progress: &mut Progress,
+    io: &mut impl CurlautOutput,
+) -> Result<u64, RequestError> {
+    let mut buffer = vec![0; BUFFER_SIZE];
+    let mut written = 0;
+    loop {
+        let read = body
+            .read(&mut buffer)
+            .map_err(|source| RequestError::from_body_read(url, source))?;
+        if read == 0 {
+            break;
+        }
+        file.write_all(&buffer[..read])
+            .map_err(|source| RequestError::File {
+                path: path.to_owned(),
+                source,
+            })?;
+        written += read as u64;
+        progress.advance(read as u64, io)?;
+    }
+    progress.finish(io)?;
+    Ok(written)
+}
+
+fn local_path(
+    download: &HttpDownload,
+    url: &Url,
+    headers: Option<&HeaderMap>,
+) -> Result<PathBuf, RequestError> {
+    match &download.file {
+        DownloadFile::Path(path) => Ok(path.clone()),
+        DownloadFile::RemoteName => headers
+            .and_then(|headers| headers.get(CONTENT_DISPOSITION))
+            .and_then(|value| value.to_str().ok())
+            .and_then(file_name_from_content_disposition)
+            .or_else(|| file_name_from_url(url))
+            .map(PathBuf::from)
+            .ok_or_else(|| RequestError::NoFileName(url.clone())),
+    }
+}
+
+/// Supports `filename="name"`, `filename=name` and `filename*=UTF-8''name` (preferred).
+fn file_name_from_content_disposition(value: &str) -> Option<String> {
+    let mut file_name = None;
+    for param in value.split(';').map(str::trim) {
+        let Some((name, value)) = param.split_once('=') else {
+            continue;
+        };
+        match name.trim().to_ascii_lowercase().as_str() {
+            "filename*" => {
+                let (_charset, encoded) = value.trim().split_once("''")?;
+                let decoded = percent_decode_str(encoded).decode_utf8().ok()?;
+                return sanitize_file_name(&decoded);
+            }
+            "filename" => file_name = sanitize_file_name(value.trim().trim_matches('"')),
+            _ => {}
+        }
+    }
+    file_name
+}
+
+fn file_name_from_url(url: &Url) -> Option<String> {
+    let segment = url.path_segments()?.next_back()?;
+    let decoded = percent_decode_str(segment).decode_utf8().ok()?;
+    sanitize_file_name(&decoded)
+}
+
+/// Server must not be able to write outside of current directory.
+fn sanitize_file_name(name: &str) -> Option<String> {
+    let name = name.rsplit(['/', '\\']).next()?.trim();
+    match name {
+        "" | "." | ".." => None,
+        name => Some(name.to_owned()),
+    }
+}
+
+struct Progress {
+    downloaded: u64,
+    total: Option<u64>,
+    last_report: Option<Instant>,
+}
+
+impl Progress {
+    fn new(downloaded: u64, total: Option<u64>) -> Self {
+        Self {
+            downloaded,
+            total,
+            last_report: None,
+        }
+    }
+
+    fn advance(&mut self, bytes: u64, io: &mut impl CurlautOutput) -> std::io::Result<()> {
+        self.downloaded += bytes;
+        let report_due = self
+            .last_report
+            .is_none_or(|last| last.elapsed() >= PROGRESS_INTERVAL);
+        if report_due {
+            self.report(io)?;
+            self.last_report = Some(Instant::now());
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, io: &mut impl CurlautOutput) -> std::io::Result<()> {
+        self.report(io)?;
+        writeln!(io.progress())
+    }
+
+    fn report(&self, io: &mut impl CurlautOutput) -> std::io::Result<()> {
+        match self.total {
+            Some(total) if total > 0 => write!(
+                io.progress(),
+                "\r{} / {} bytes ({}%)",
+                self.downloaded,
+                total,
+                self.downloaded * 100 / total
+            ),
+            _ => write!(io.progress(), "\r{} bytes", self.downloaded),
+        }?;
+        io.progress().flush()
+    }
+}
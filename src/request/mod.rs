@@ -1,2 +1,11 @@
+pub mod cookies;
+pub mod decoding;
+pub mod download;
+pub mod request_error;
 pub mod request_executor;
 pub mod request_spec;
+pub mod proxy;
+pub mod retry;
+pub mod routing;
+pub mod timings;
+pub mod tls;
@@ -0,0 +1,146 @@
+use crate::request::request_error::RequestError;
+use hyper_util::client::proxy::matcher::Matcher;
+use reqwest::blocking::ClientBuilder;
+use reqwest::{NoProxy, Proxy};
+use url::Url;
+
+/// Proxy settings of an HTTP client, unset ones are taken from the environment like curl does:
+/// `HTTP_PROXY`, `HTTPS_PROXY`, `ALL_PROXY` and `NO_PROXY`.
+#[derive(Debug, Clone, Default)]
+pub struct ProxyOptions {
+    /// http, https, socks5 or socks5h URL, scheme defaults to http,
+    /// empty to ignore proxy environment variables
+    pub proxy: Option<String>,
+    /// `user:password`, credentials may also be in the proxy URL
+    pub proxy_user: Option<String>,
+    /// hosts, domains with subdomains, IPs or networks separated by ',', '*' for all
+    pub no_proxy: Option<String>,
+}
+
+impl ProxyOptions {
+    /// Reads the environment for unset options.
+    pub(crate) fn rules(&self) -> Result<ProxyRules, RequestError> {
+        let (http, https) = match self.proxy.as_deref() {
+            Some("") => (None, None),
+            Some(proxy) => {
+                let proxy = self.parse_proxy(proxy)?;
+                (Some(proxy.clone()), Some(proxy))
+            }
+            None => {
+                let all = first_env(&["ALL_PROXY", "all_proxy"]);
+                let http = first_env(&["HTTP_PROXY", "http_proxy"]).or_else(|| all.clone());
+                let https = first_env(&["HTTPS_PROXY", "https_proxy"]).or(all);
+                let parse = |proxy: String| self.parse_proxy(&proxy);
+                (http.map(parse).transpose()?, https.map(parse).transpose()?)
+            }
+        };
+        let no_proxy = self
+            .no_proxy
+            .clone()
+            .or_else(|| first_env(&["NO_PROXY", "no_proxy"]))
+            .unwrap_or_default();
+        Ok(ProxyRules {
+            http,
+            https,
+            no_proxy,
+        })
+    }
+
+    fn parse_proxy(&self, proxy: &str) -> Result<Url, RequestError> {
+        let mut url = parse_proxy_url(proxy)?;
+        if let Some(proxy_user) = &self.proxy_user {
+            let (user, password) = proxy_user.split_once(':').unwrap_or((proxy_user, ""));
+            let invalid = || RequestError::InvalidProxy {
+                proxy: proxy.to_owned(),
+                reason: "can't have credentials".to_owned(),
+            };
+            url.set_username(user).map_err(|_| invalid())?;
+            url.set_password(Some(password)).map_err(|_| invalid())?;
+        }
+        Ok(url)
+    }
+}
+
+/// Validates proxy URL, curl takes proxy without scheme as http one.
+pub fn parse_proxy_url(proxy: &str) -> Result<Url, RequestError> {
+    let invalid = |reason: String| RequestError::InvalidProxy {
+        proxy: proxy.to_owned(),
+        reason,
+    };
+    let url = if proxy.contains("://") {
+        Url::parse(proxy)
+    } else {
+        Url::parse(&format!("http://{proxy}"))
+    };
+    let url = url.map_err(|err| invalid(err.to_string()))?;
+    match url.scheme() {
+        "http" | "https" | "socks5" | "socks5h" => Ok(url),
+        scheme => Err(invalid(format!("unsupported scheme '{scheme}'"))),
+    }
+}
+
+fn first_env(names: &[&str]) -> Option<String> {
+    names
+        .iter()
+        .find_map(|name| std::env::var(name).ok())
+        .filter(|value| !value.is_empty())
+}
+
+/// Resolved proxies, the client and verbose output use the same rules.
+#[derive(Debug, Clone)]
+pub(crate) struct ProxyRules {
+    http: Option<Url>,
+    https: Option<Url>,
+    no_proxy: String,
+}
+
+impl ProxyRules {
+    /// Proxy used for the URL, `None` to connect directly.
+    pub fn proxy_for(&self, url: &Url) -> Option<&Url> {
+        let uri = url.as_str().parse::<http::Uri>().ok()?;
+        let mut matcher = Matcher::builder().no(self.no_proxy.as_str());
+        if let Some(http) = &self.http {
+            matcher = matcher.http(http.as_str());
+        }
+        if let Some(https) = &self.https {
+            matcher = matcher.https(https.as_str());
+        }
+        matcher.build().intercept(&uri)?;
+        match url.scheme() {
+            "https" => self.https.as_ref(),
+            _ => self.http.as_ref(),
+        }
+    }
+
+    pub fn apply(&self, mut builder: ClientBuilder) -> Result<ClientBuilder, RequestError> {
+        // the environment is already read, reqwest must not do it on its own
+        builder = builder.no_proxy();
+        if let Some(http) = &self.http {
+            builder = builder.proxy(self.reqwest_proxy(Proxy::http(http.as_str()), http)?);
+        }
+        if let Some(https) = &self.https {
+            builder = builder.proxy(self.reqwest_proxy(Proxy::https(https.as_str()), https)?);
+        }
+        Ok(builder)
+    }
+
+    fn reqwest_proxy(
+        &self,
+        proxy: reqwest::Result<Proxy>,
+        url: &Url,
+    ) -> Result<Proxy, RequestError> {
+        let proxy = proxy.map_err(|err| RequestError::InvalidProxy {
+            proxy: without_credentials(url),
+            reason: err.to_string(),
+        })?;
+        Ok(proxy.no_proxy(NoProxy::from_string(&self.no_proxy)))
+    }
+}
+
+/// Proxy URL to be printed.
+pub fn without_credentials(proxy: &Url) -> String {
+    let mut proxy = proxy.clone();
+    let _ = proxy.set_username("");
+    let _ = proxy.set_password(None);
+    proxy.to_string()
+}
@@ -0,0 +1,129 @@
+use crate::output::select::SelectError;
+use std::path::PathBuf;
+use url::Url;
+
+/// Failure to build, send or handle an HTTP request to the target.
+#[derive(Debug, thiserror::Error)]
+pub enum RequestError {
+    #[error("Invalid header '{0}', expected 'Name: value'")]
+    InvalidHeader(String),
+    #[error("Invalid header name '{0}'")]
+    InvalidHeaderName(String),
+    #[error("Invalid value of header '{0}'")]
+    InvalidHeaderValue(String),
+    #[error("No host or port specified in {0}")]
+    NoHost(Url),
+    #[error("Failed to resolve host {host}")]
+    Resolve {
+        host: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("Can't connect elsewhere than IP address of {0}, --resolve and --connect-to need host name")]
+    Reroute(Url),
+    #[error("Failed to build http client")]
+    Client(#[source] reqwest::Error),
+    #[error("Invalid TLS configuration")]
+    Tls(#[source] rustls::Error),
+    #[error("Can't read certificate file {path}")]
+    CertificateFile {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("Invalid certificate file {path}: {reason}")]
+    InvalidCertificate { path: PathBuf, reason: String },
+    #[error("Invalid proxy '{proxy}': {reason}")]
+    InvalidProxy { proxy: String, reason: String },
+    #[error("Invalid public key pin '{0}', expected 'sha256//<base64>'")]
+    InvalidPin(String),
+    #[error("Public key of {url} doesn't match pinned keys, observed {observed}")]
+    PinnedPublicKey {
+        url: Url,
+        observed: String,
+        #[source]
+        source: reqwest::Error,
+    },
+    #[error("Failed to build http request")]
+    Build(#[source] reqwest::Error),
+    #[error("Failed to connect to {url}")]
+    Connect {
+        url: Url,
+        #[source]
+        source: reqwest::Error,
+    },
+    #[error("Connecting to {url} timed out")]
+    ConnectTimeout {
+        url: Url,
+        #[source]
+        source: reqwest::Error,
+    },
+    #[error("Request to {url} timed out")]
+    Timeout {
+        url: Url,
+        #[source]
+        source: reqwest::Error,
+    },
+    #[error("Failed to read response body from {url}")]
+    Body {
+        url: Url,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("Request to {url} failed")]
+    Transport {
+        url: Url,
+        #[source]
+        source: reqwest::Error,
+    },
+    #[error("Maximum ({max_redirects}) redirects followed, next one from {url}")]
+    TooManyRedirects { url: Url, max_redirects: u32 },
+    /// Response status is 4xx or 5xx and request was asked to fail on it.
+    #[error("HTTP request failed with status {status}")]
+    Status { status: u16 },
+    #[error(transparent)]
+    Select(#[from] SelectError),
+    #[error("Can't get file name from {0}, use --output")]
+    NoFileName(Url),
+    #[error("Can't use cookie file {path}")]
+    CookieFile {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("Can't write file {path}")]
+    File {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("Failed to write output")]
+    Output(#[from] std::io::Error),
+}
+
+impl RequestError {
+    /// Classifies error of sending request or reading response.
+    pub fn from_reqwest(url: &Url, source: reqwest::Error) -> RequestError {
+        let url = url.clone();
+        if source.is_connect() && source.is_timeout() {
+            RequestError::ConnectTimeout { url, source }
+        } else if source.is_timeout() {
+            RequestError::Timeout { url, source }
+        } else if source.is_connect() {
+            RequestError::Connect { url, source }
+        } else {
+            RequestError::Transport { url, source }
+        }
+    }
+
+    /// Blocking response wraps errors like timeouts into `io::Error` when read as `Read`.
+    pub fn from_body_read(url: &Url, source: std::io::Error) -> RequestError {
+        match source.downcast::<reqwest::Error>() {
+            Ok(err) => RequestError::from_reqwest(url, err),
+            Err(source) => RequestError::Body {
+                url: url.clone(),
+                source,
+            },
+        }
+    }
+}
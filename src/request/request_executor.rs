@@ -1,74 +1,459 @@
 use crate::auth::HttpAuthorization;
+use crate::keycloak::keycloak_route::KeycloakRoute;
+use crate::output::envelope::{EnvelopeRequest, EnvelopeResponse, JsonEnvelope, OutputFormat};
+use crate::output::har::{HarEntry, HarRecorder, HarRequest, HarResponse};
+use crate::output::pretty;
+use crate::output::select::JsonSelector;
+use crate::output::write_out::{write_timing_table, ExchangeSummary};
 use crate::output::CurlautOutput;
+use crate::request::cookies::CookieJar;
+use crate::request::decoding::{self, ResponseBody};
+use crate::request::download;
+use crate::request::request_error::RequestError;
 use crate::request::request_spec::{
-    HttpRequestBody, HttpRequestHeaders, HttpRequestMethod, HttpRequestSpec,
+    HttpFailMode, HttpRequestBody, HttpRequestHeaders, HttpRequestMethod, HttpRequestSpec,
 };
-use anyhow::Context;
+use crate::request::proxy::{without_credentials, ProxyRules};
+use crate::request::retry;
+use crate::request::routing::{self, Route};
+use crate::request::timings::{ConnectTimer, HttpTimings};
+use crate::request::tls::{self, CertificateSummary, TlsRecorder};
 use reqwest::blocking::{Client, Request, RequestBuilder, Response};
-use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
-use reqwest::Method;
-use std::io::Write;
-use std::net::{IpAddr, ToSocketAddrs};
-use url::Host;
+use reqwest::header::{
+    HeaderMap, HeaderValue, ACCEPT_ENCODING, AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE, COOKIE, HOST,
+    LOCATION, RANGE,
+};
+use reqwest::redirect::Policy;
+use reqwest::tls::TlsInfo;
+use reqwest::{Method, StatusCode, Version};
+use std::collections::HashSet;
+use std::io::{Read, Write};
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Instant, SystemTime};
+use url::{Host, Position, Url};
 
-pub fn execute(request_spec: HttpRequestSpec, io: &mut impl CurlautOutput) -> anyhow::Result<()> {
-    log_target_host(&request_spec, io)?;
-    log_request_starts(&request_spec, io)?;
+/// Exchanges are recorded to `har` if given, every redirect hop as its own entry.
+pub fn execute(
+    request_spec: HttpRequestSpec,
+    mut har: Option<&mut HarRecorder>,
+    io: &mut impl CurlautOutput,
+) -> Result<(), RequestError> {
+    let started = Instant::now();
+    let started_at = SystemTime::now();
+    let proxy_rules = request_spec.proxy.rules()?;
+    let unix_socket = unix_socket(&request_spec);
+    let proxy = match unix_socket {
+        Some(_) => None,
+        None => proxy_rules.proxy_for(&request_spec.url),
+    };
+    let route = match proxy {
+        // the proxy connects to the target, overrides apply only to direct connections
+        Some(_) => routing::route(&request_spec.url, &[], &[])?,
+        None => routing::route(
+            &request_spec.url,
+            &request_spec.resolve,
+            &request_spec.connect_to,
+        )?,
+    };
+    match unix_socket {
+        Some(path) => writeln!(
+            io.verbose(),
+            "* Host {}:{} via unix socket {}",
+            route.host,
+            route.port,
+            path.display()
+        )?,
+        None => log_target_host(&request_spec.url, proxy, &route, io)?,
+    }
 
-    let http_client = build_http_client(&request_spec)?;
+    let connect_timer = ConnectTimer::new(started);
+    let tls_recorder = TlsRecorder::default();
+    let cookie_jar = request_spec.cookies.load()?;
+    let http_client = build_http_client(
+        &request_spec,
+        &proxy_rules,
+        &route,
+        cookie_jar.as_ref(),
+        &connect_timer,
+        &tls_recorder,
+    )?;
 
     // start to build http request
+    let url = request_spec.url.clone();
     let mut rb = http_client.request(to_reqwest_method(&request_spec.method), request_spec.url);
-    rb = add_headers(rb, request_spec.headers);
-    rb = add_auth(rb, request_spec.authorization);
+    if let Some(authorization) = request_spec.authorization {
+        rb = add_auth(rb, authorization);
+    }
     rb = add_body(rb, request_spec.body);
-    let request = rb.build().with_context(|| "Failed to build http request")?;
+    let mut request = rb.build().map_err(RequestError::Build)?;
+    if request_spec.compressed {
+        let accept_encoding = HeaderValue::from_static(decoding::ACCEPT_ENCODING);
+        request.headers_mut().insert(ACCEPT_ENCODING, accept_encoding);
+    }
+    // user headers go last to be able to override or remove defaults
+    add_headers(&mut request, request_spec.headers);
+    // the client doesn't add jar cookies to a request which has its own
+    let mut user_cookie = request.headers().contains_key(COOKIE);
+    let mut fixed_cookies = true;
+    let resume_offset = match &request_spec.download {
+        Some(download) => download::resume_offset(download, &url)?,
+        None => None,
+    };
+    if let Some(offset) = resume_offset {
+        let range = HeaderValue::from_str(&format!("bytes={offset}-"))
+            .expect("range header must be valid");
+        request.headers_mut().insert(RANGE, range);
+    }
+
+    // the last hop is the response, redirects before it are only logged and recorded to HAR
+    let mut redirects = 0;
+    let (mut hop_started, mut hop_started_at) = (started, started_at);
+    let (effective_url, envelope_request, har_request, response) = loop {
+        let hop_url = request.url().clone();
+        if let (Some(jar), false) = (&cookie_jar, user_cookie) {
+            // the same ones the client sends, added here to be logged and recorded
+            request.headers_mut().remove(COOKIE);
+            if let Some(cookies) = jar.header(&hop_url, fixed_cookies) {
+                request.headers_mut().insert(COOKIE, cookies);
+            }
+        }
+        let envelope_request = (request_spec.output_format == OutputFormat::Json)
+            .then(|| EnvelopeRequest::new(request.method(), request.url(), request.headers()));
+        let har_request = har.is_some().then(|| HarRequest::new(&request));
+        // logged after sending, when the negotiated protocol is known
+        let request_log = RequestLog::new(&request);
+        // cloned before rerouting, bodies are always in memory
+        let next_request = request_spec.location.and_then(|_| request.try_clone());
+        reroute(&mut request, &url, &route);
 
-    log_request_content(&request, io)?;
+        // do execute request
+        let idempotent = retry::is_idempotent(request.method());
+        let result =
+            retry::execute_with_retry(&http_client, request, &request_spec.retry, idempotent, io)?;
+        if let Ok(response) = &result {
+            log_connection(response, &tls_recorder, io)?;
+        }
+        log_request(&request_log, result.as_ref().ok().map(Response::version), io)?;
+        let response = result.map_err(|source| match tls_recorder.pin_mismatch() {
+            Some(observed) => RequestError::PinnedPublicKey {
+                url: hop_url.clone(),
+                observed: observed.to_string(),
+                source,
+            },
+            None => RequestError::from_reqwest(&hop_url, source),
+        })?;
 
-    // do execute request
-    let response = http_client
-        .execute(request)
-        .with_context(|| "Failed to execute http request")?;
+        // log response
+        log_response_head(&response, io)?;
+        let head_version = format!("{:?}", response.version());
+        pretty::write_head(io.head(), &head_version, response.status(), response.headers())?;
+        let next_request = match (request_spec.location, next_request) {
+            (Some(location), Some(next_request)) => redirect_request(
+                next_request,
+                &response,
+                location.trusted,
+                &request_spec.token_routes,
+            ),
+            _ => None,
+        };
+        let Some(next_request) = next_request else {
+            break (hop_url, envelope_request, har_request, response);
+        };
+        if !request_spec.location.is_some_and(|location| location.trusted)
+            && !same_host(&hop_url, next_request.url())
+        {
+            // the user's cookies are for the first host, the jar ones still go where they match
+            user_cookie = false;
+            fixed_cookies = false;
+        }
+        let max_redirects = request_spec.location.map_or(0, |location| location.max_redirects);
+        if redirects == max_redirects {
+            return Err(RequestError::TooManyRedirects {
+                url: hop_url,
+                max_redirects,
+            });
+        }
+        if let (Some(recorder), Some(request)) = (har.as_deref_mut(), har_request) {
+            let (status, version, headers) =
+                (response.status(), response.version(), response.headers());
+            let response = HarResponse::new(status, version, headers, None, 0, 0);
+            let timings = HttpTimings {
+                auth: None,
+                // the connection of the first hop may be reused by the next ones
+                connect: (redirects == 0).then(|| connect_timer.connected()).flatten(),
+                start_transfer: Some(hop_started.elapsed()),
+                total: hop_started.elapsed(),
+            };
+            recorder.record(HarEntry::new(hop_started_at, request, response, &timings))?;
+        }
+        redirects += 1;
+        writeln!(io.verbose(), "* Issue another request to this URL: '{}'", next_request.url())?;
+        request = next_request;
+        (hop_started, hop_started_at) = (Instant::now(), SystemTime::now());
+    };
+    let start_transfer = started.elapsed();
+    let status = response.status();
+    let version = response.version();
+    let headers = response.headers().clone();
+    let content_type = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_owned());
+    let decode = request_spec.compressed && !request_spec.raw;
+    let mut response_body = ResponseBody::new(response, decode)
+        .map_err(|err| RequestError::from_body_read(&effective_url, err))?;
+    let is_error_status = status.is_client_error() || status.is_server_error();
+    let print_body = !is_error_status || request_spec.fail != HttpFailMode::WithoutBody;
+    let nothing_to_resume = resume_offset.is_some() && status == StatusCode::RANGE_NOT_SATISFIABLE;
+    let mut envelope_response = None;
+    // body is kept in memory unless it's saved to file
+    let (decoded_size, body) = if nothing_to_resume {
+        writeln!(io.verbose(), "* Nothing to resume, file is already complete")?;
+        (0, None)
+    } else if envelope_request.is_some() {
+        let mut body = Vec::new();
+        response_body
+            .read_to_end(&mut body)
+            .map_err(|err| RequestError::from_body_read(&effective_url, err))?;
+        envelope_response = Some(EnvelopeResponse::new(status, &headers, &body));
+        (body.len() as u64, Some(body))
+    } else if print_body && !request_spec.head_only {
+        match &request_spec.download {
+            Some(download) => {
+                let body = &mut response_body;
+                let written = download::save_body(
+                    status,
+                    &headers,
+                    body,
+                    download,
+                    &url,
+                    resume_offset,
+                    io,
+                )?;
+                (written, None)
+            }
+            None => {
+                let select = request_spec.select.as_ref();
+                let content_type = content_type.as_deref();
+                let raw_output = request_spec.raw_output;
+                let url = &effective_url;
+                let body = &mut response_body;
+                let bytes =
+                    write_response_body(body, url, content_type, select, raw_output, io)?;
+                (bytes.len() as u64, Some(bytes))
+            }
+        }
+    } else {
+        (0, None)
+    };
+    if let Some(jar) = &cookie_jar {
+        request_spec.cookies.save(jar)?;
+    }
+    let size_download = response_body.received();
+    writeln!(io.verbose(), "{{ [{size_download} bytes data]")?;
+    if let Some(encoding) = response_body.decoded() {
+        writeln!(
+            io.verbose(),
+            "* Decoded {encoding}: {size_download} bytes received, {decoded_size} bytes decoded"
+        )?;
+    }
 
-    // log response
-    log_response(response, io)?;
+    let summary = ExchangeSummary {
+        url: &effective_url,
+        status,
+        content_type: content_type.as_deref(),
+        size_download,
+        timings: HttpTimings {
+            auth: request_spec.auth_info.as_ref().map(|auth| auth.time),
+            connect: connect_timer.connected(),
+            start_transfer: Some(start_transfer),
+            total: started.elapsed(),
+        },
+    };
+    if let (Some(recorder), Some(request)) = (har, har_request) {
+        let body = body.as_deref();
+        let response =
+            HarResponse::new(status, version, &headers, body, decoded_size, size_download);
+        // earlier hops have their own entries, this one starts with the last hop
+        let hop_offset = hop_started.duration_since(started);
+        let timings = &summary.timings;
+        let timings = HttpTimings {
+            auth: timings.auth.filter(|_| redirects == 0),
+            connect: timings.connect.filter(|_| redirects == 0),
+            start_transfer: timings.start_transfer.map(|time| time.saturating_sub(hop_offset)),
+            total: timings.total.saturating_sub(hop_offset),
+        };
+        recorder.record(HarEntry::new(hop_started_at, request, response, &timings))?;
+    }
+    if let (Some(request), Some(response)) = (envelope_request, envelope_response) {
+        let envelope = JsonEnvelope {
+            request,
+            response,
+            timings: (&summary.timings).into(),
+            auth: request_spec.auth_info.as_ref().map(Into::into),
+        };
+        envelope.write(io)?;
+    }
+    if let Some(write_out) = &request_spec.write_out {
+        write_out.write(io.common(), &summary)?;
+    }
+    if request_spec.timing {
+        write_timing_table(io.summary(), &summary.timings)?;
+    }
 
+    if is_error_status && request_spec.fail != HttpFailMode::Never {
+        return Err(RequestError::Status {
+            status: status.as_u16(),
+        });
+    }
     Ok(())
 }
 
-fn build_http_client(request_spec: &HttpRequestSpec) -> anyhow::Result<Client> {
-    let mut client_builder = reqwest::blocking::ClientBuilder::new();
-    client_builder = client_builder.timeout(request_spec.timeout);
-    if !request_spec.http1 {
-        // enable alpn to be possible to upgrade to http2
-        client_builder = client_builder.use_rustls_tls();
+/// Connects to the host and port given by `--connect-to` instead of the ones of the URL,
+/// the server still sees the original host and port.
+fn reroute(request: &mut Request, url: &Url, route: &Route) {
+    let original_port = url.port_or_known_default();
+    let is_target = request.url().host() == url.host()
+        && request.url().port_or_known_default() == original_port;
+    if !is_target || Some(route.port) == original_port {
+        return;
+    }
+    let authority = HeaderValue::from_str(&url[Position::BeforeHost..Position::AfterPort])
+        .expect("URL authority must be valid header value");
+    request.headers_mut().entry(HOST).or_insert(authority);
+    request
+        .url_mut()
+        .set_port(Some(route.port))
+        .expect("http URL must have port");
+}
+
+/// Request to `Location` of redirect response, `None` if the response is not a redirect.
+/// The token and user cookies are sent to another host or port only if trusted, like curl
+/// does, and the token never leaves the routes of the Keycloak config.
+fn redirect_request(
+    mut request: Request,
+    response: &Response,
+    trusted: bool,
+    token_routes: &[KeycloakRoute],
+) -> Option<Request> {
+    let status = response.status();
+    let keeps_method = match status {
+        StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND => request.method() != Method::POST,
+        StatusCode::SEE_OTHER => request.method() == Method::HEAD,
+        StatusCode::TEMPORARY_REDIRECT | StatusCode::PERMANENT_REDIRECT => true,
+        _ => return None,
+    };
+    let location = response.headers().get(LOCATION)?.to_str().ok()?;
+    let location = request.url().join(location).ok()?;
+    if !keeps_method {
+        *request.method_mut() = Method::GET;
+        *request.body_mut() = None;
+        request.headers_mut().remove(CONTENT_TYPE);
+        request.headers_mut().remove(CONTENT_LENGTH);
     }
-    if request_spec.insecure {
-        // ignore tls verification
-        client_builder = client_builder.danger_accept_invalid_certs(true);
+    if !same_host(request.url(), &location) {
+        request.headers_mut().remove(HOST);
+        if !trusted {
+            request.headers_mut().remove(AUTHORIZATION);
+            request.headers_mut().remove(COOKIE);
+        }
+    }
+    if !token_routes.is_empty() && !token_routes.iter().any(|route| route.matches(&location)) {
+        request.headers_mut().remove(AUTHORIZATION);
     }
-    let http_client = client_builder.build()?;
+    *request.url_mut() = location;
+    Some(request)
+}
+
+fn same_host(url: &Url, other: &Url) -> bool {
+    url.host() == other.host() && url.port_or_known_default() == other.port_or_known_default()
+}
+
+/// Socket the client connects to instead of TCP, only Unix has them.
+#[cfg(unix)]
+fn unix_socket(request_spec: &HttpRequestSpec) -> Option<&Path> {
+    request_spec.unix_socket.as_deref()
+}
+
+#[cfg(not(unix))]
+fn unix_socket(_request_spec: &HttpRequestSpec) -> Option<&Path> {
+    None
+}
+
+fn build_http_client(
+    request_spec: &HttpRequestSpec,
+    proxy_rules: &ProxyRules,
+    route: &Route,
+    cookie_jar: Option<&Arc<CookieJar>>,
+    connect_timer: &ConnectTimer,
+    tls_recorder: &TlsRecorder,
+) -> Result<Client, RequestError> {
+    let timeouts = request_spec.timeouts;
+    // read and total timeouts of async client cover the body, blocking one only waits for it
+    let mut async_builder = reqwest::ClientBuilder::new();
+    if let Some(read) = timeouts.read {
+        async_builder = async_builder.read_timeout(read);
+    }
+    if let Some(total) = timeouts.total {
+        async_builder = async_builder.timeout(total);
+    }
+    let mut client_builder = reqwest::blocking::ClientBuilder::from(async_builder);
+    client_builder = client_builder.timeout(timeouts.total);
+    client_builder = client_builder.connect_timeout(timeouts.connect);
+    // followed by `execute` to log every hop
+    client_builder = client_builder.redirect(Policy::none());
+    client_builder = client_builder.connector_layer(connect_timer.clone());
+    if let Some(cookie_jar) = cookie_jar {
+        // stores `Set-Cookie` of every hop, including redirects
+        client_builder = client_builder.cookie_provider(cookie_jar.clone());
+    }
+    client_builder = proxy_rules.apply(client_builder)?;
+    #[cfg(unix)]
+    if let Some(path) = &request_spec.unix_socket {
+        // proxies and DNS are ignored by the client
+        client_builder = client_builder.unix_socket(path.as_path());
+    }
+    if let (Some(addrs), Some(Host::Domain(domain))) = (&route.addrs, request_spec.url.host()) {
+        let addrs: Vec<SocketAddr> = addrs
+            .iter()
+            .map(|addr| SocketAddr::new(*addr, route.port))
+            .collect();
+        client_builder = client_builder.resolve_to_addrs(domain, &addrs);
+    }
+    if request_spec.http1 {
+        client_builder = client_builder.http1_only();
+    }
+    let tls_config = tls::client_config(&request_spec.tls, request_spec.http1, tls_recorder)?;
+    client_builder = client_builder.use_preconfigured_tls(tls_config);
+    // peer certificate for verbose output
+    client_builder = client_builder.tls_info(true);
+    let http_client = client_builder.build().map_err(RequestError::Client)?;
     Ok(http_client)
 }
 
-fn log_target_host(request: &HttpRequestSpec, io: &mut impl CurlautOutput) -> anyhow::Result<()> {
-    let host = request
-        .url
-        .host()
-        .ok_or_else(|| anyhow::anyhow!("No host specified"))?;
-    let port = request
-        .url
-        .port_or_known_default()
-        .ok_or_else(|| anyhow::anyhow!("No port specified"))?;
+/// Addresses are the ones the client connects to, overridden by `--resolve` or `--connect-to`.
+fn log_target_host(
+    url: &Url,
+    proxy: Option<&Url>,
+    route: &Route,
+    io: &mut impl CurlautOutput,
+) -> Result<(), RequestError> {
+    let no_host = || RequestError::NoHost(url.clone());
+    let host = url.host().ok_or_else(no_host)?;
+    let port = url.port_or_known_default().ok_or_else(no_host)?;
+    if let Some(proxy) = proxy {
+        // the target may be resolvable only by the proxy
+        writeln!(io.verbose(), "* Host {host}:{port} via proxy {}", without_credentials(proxy))?;
+        return Ok(());
+    }
     match host {
         Host::Domain(host) => {
             writeln!(io.verbose(), "* Host {host}")?;
-            let addr = (host, port)
-                .to_socket_addrs()
-                .with_context(|| format!("Failed to resolve host {host}"))?;
-            let ips: (Vec<IpAddr>, Vec<IpAddr>) =
-                addr.map(|addr| addr.ip()).partition(|ip| ip.is_ipv4());
+            // the client resolves the others itself, the address it used is logged when connected
+            let addrs = route.addrs.clone().unwrap_or_default();
+            let ips: (Vec<IpAddr>, Vec<IpAddr>) = addrs.into_iter().partition(IpAddr::is_ipv4);
             if !ips.0.is_empty() {
                 writeln!(io.verbose(), "* IPv4: {:?}", ips.0)?;
             }
@@ -79,42 +464,105 @@ fn log_target_host(request: &HttpRequestSpec, io: &mut impl CurlautOutput) -> an
         Host::Ipv4(addr) => writeln!(io.verbose(), "* Host (IPv4): {addr}")?,
         Host::Ipv6(addr) => writeln!(io.verbose(), "* Host (ipv6): {addr}")?,
     }
+    if route.rerouted {
+        writeln!(io.verbose(), "* Connecting to {}:{} instead", route.host, route.port)?;
+    }
     Ok(())
 }
 
-fn log_request_starts(
-    request_spec: &HttpRequestSpec,
+/// What is printed about the request, kept because the request is consumed by sending.
+struct RequestLog {
+    method: Method,
+    url: Url,
+    headers: HeaderMap,
+    bytes_sent: usize,
+}
+
+impl RequestLog {
+    fn new(request: &Request) -> Self {
+        Self {
+            method: request.method().clone(),
+            url: request.url().clone(),
+            headers: request.headers().clone(),
+            bytes_sent: request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .map(|bytes| bytes.len())
+                .unwrap_or(0),
+        }
+    }
+}
+
+/// `version` is `None` if the request failed before the protocol was negotiated.
+fn log_request(
+    request: &RequestLog,
+    version: Option<Version>,
     io: &mut impl CurlautOutput,
-) -> anyhow::Result<()> {
-    writeln!(
-        io.verbose(),
-        "> {} {} HTTP/1.1",
-        request_spec.method,
-        request_spec.url
-    )?;
+) -> Result<(), RequestError> {
+    match version {
+        Some(version) => writeln!(io.verbose(), "> {} {} {version:?}", request.method, request.url)?,
+        None => writeln!(io.verbose(), "> {} {}", request.method, request.url)?,
+    }
+    let style = io.verbose_style();
+    for (key, value) in &request.headers {
+        pretty::write_header(io.verbose(), style, '>', key.as_str(), value.as_bytes())?;
+    }
+    writeln!(io.verbose(), ">")?;
+    writeln!(io.verbose(), "}} [{} bytes data]", request.bytes_sent)?;
     Ok(())
 }
 
-fn log_request_content(request: &Request, io: &mut impl CurlautOutput) -> anyhow::Result<()> {
-    for (key, value) in request.headers() {
-        writeln!(io.verbose(), "> {key:?}: {value:?}")?;
+fn log_connection(
+    response: &Response,
+    tls_recorder: &TlsRecorder,
+    io: &mut impl CurlautOutput,
+) -> Result<(), RequestError> {
+    if let Some(addr) = response.remote_addr() {
+        writeln!(io.verbose(), "* Connected to {} port {}", addr.ip(), addr.port())?;
+    }
+    let Some(session) = tls_recorder.session() else {
+        // plain http
+        return Ok(());
+    };
+    writeln!(io.verbose(), "* SSL connection using {session}")?;
+    let alpn = match response.version() {
+        Version::HTTP_2 => "h2",
+        _ => "http/1.1",
+    };
+    writeln!(io.verbose(), "* ALPN: using {alpn}")?;
+    let certificate = response
+        .extensions()
+        .get::<TlsInfo>()
+        .and_then(TlsInfo::peer_certificate)
+        .and_then(CertificateSummary::from_der);
+    if let Some(certificate) = certificate {
+        writeln!(io.verbose(), "* Server certificate:")?;
+        writeln!(io.verbose(), "*  subject: {}", certificate.subject)?;
+        writeln!(io.verbose(), "*  expire date: {}", certificate.not_after)?;
+        writeln!(io.verbose(), "*  issuer: {}", certificate.issuer)?;
+        writeln!(io.verbose(), "*  public key: {}", certificate.public_key_pin)?;
     }
-    writeln!(io.verbose(), ">")?;
-    let bytes_sent = request
-        .body()
-        .map(|body| body.as_bytes())
-        .flatten()
-        .map(|bytes| bytes.len())
-        .unwrap_or(0);
-    writeln!(io.verbose(), "}} [{bytes_sent} bytes data]")?;
     Ok(())
 }
 
-fn add_headers(mut rb: RequestBuilder, headers: HttpRequestHeaders) -> RequestBuilder {
-    for (header_name, header_value) in headers.0.iter() {
-        rb = rb.header(*header_name, *header_value);
+fn add_headers(request: &mut Request, headers: HttpRequestHeaders) {
+    let mut overridden = HashSet::new();
+    let request_headers = request.headers_mut();
+    for (header_name, header_value) in headers {
+        match header_value {
+            None => {
+                request_headers.remove(&header_name);
+                overridden.remove(&header_name);
+            }
+            Some(header_value) => {
+                // the first occurrence replaces default value, next ones are appended
+                if overridden.insert(header_name.clone()) {
+                    request_headers.remove(&header_name);
+                }
+                request_headers.append(header_name, header_value);
+            }
+        }
     }
-    rb
 }
 
 fn add_auth(rb: RequestBuilder, http_auth: Box<dyn HttpAuthorization>) -> RequestBuilder {
@@ -139,21 +587,37 @@ fn to_reqwest_method(method: &HttpRequestMethod) -> Method {
     }
 }
 
-fn log_response(response: Response, io: &mut impl CurlautOutput) -> anyhow::Result<()> {
-    let response_status = &response.status();
-    writeln!(io.verbose(), "< {:?} {response_status}", response.version())?;
+fn log_response_head(response: &Response, io: &mut impl CurlautOutput) -> Result<(), RequestError> {
+    let style = io.verbose_style();
+    let version = format!("{:?}", response.version());
+    pretty::write_status_line(io.verbose(), style, &version, response.status())?;
     for (key, value) in response.headers() {
-        writeln!(io.verbose(), "< {key:?}: {value:?}")?;
+        pretty::write_header(io.verbose(), style, '<', key.as_str(), value.as_bytes())?;
     }
     writeln!(io.verbose(), "<")?;
-    writeln!(
-        io.verbose(),
-        "{{ [{} bytes data]",
-        response.content_length().unwrap_or(0) // todo doesn't work
-    )?;
-    let text = response
-        .text()
-        .with_context(|| "Failed to parse response body")?;
-    write!(io.common(), "{text}")?;
     Ok(())
 }
+
+/// Returns decoded body.
+fn write_response_body(
+    body: &mut ResponseBody,
+    url: &Url,
+    content_type: Option<&str>,
+    select: Option<&JsonSelector>,
+    raw_output: bool,
+    io: &mut impl CurlautOutput,
+) -> Result<Vec<u8>, RequestError> {
+    let mut bytes = Vec::new();
+    body.read_to_end(&mut bytes)
+        .map_err(|err| RequestError::from_body_read(url, err))?;
+    let style = io.common_style();
+    match select {
+        Some(selector) => {
+            let values = selector.apply_to_str(&pretty::decode_text(&bytes, content_type))?;
+            pretty::write_selected(io.common(), style, &values, raw_output)?;
+        }
+        None => pretty::write_body(io.common(), style, content_type, &bytes)?,
+    }
+    Ok(bytes)
+}
+
@@ -1,18 +1,101 @@
-use crate::auth::HttpAuthorization;
-use std::collections::HashMap;
+use crate::auth::{AuthInfo, HttpAuthorization};
+use crate::keycloak::keycloak_route::KeycloakRoute;
+use crate::output::envelope::OutputFormat;
+use crate::output::select::JsonSelector;
+use crate::output::write_out::WriteOut;
+use crate::request::cookies::CookieOptions;
+use crate::request::request_error::RequestError;
+use crate::request::proxy::ProxyOptions;
+use crate::request::retry::RetryPolicy;
+use crate::request::routing::{ConnectTo, ResolveOverride};
+use crate::request::tls::TlsOptions;
+use reqwest::header::{HeaderName, HeaderValue};
 use std::fmt::Display;
+use std::path::PathBuf;
 use std::time::Duration;
 use url::Url;
 
-pub struct HttpRequestSpec<'a> {
+pub struct HttpRequestSpec {
     pub url: Url,
     pub method: HttpRequestMethod,
-    pub headers: HttpRequestHeaders<'a>,
+    pub headers: HttpRequestHeaders,
     pub body: HttpRequestBody,
-    pub authorization: Box<dyn HttpAuthorization>,
+    pub authorization: Option<Box<dyn HttpAuthorization>>, // no `Authorization` header if not set
+    pub token_routes: Vec<KeycloakRoute>, // if not empty, token is sent only to matching urls
     pub http1: bool,
-    pub timeout: Duration,
-    pub insecure: bool,
+    pub timeouts: HttpTimeouts,
+    pub tls: TlsOptions,
+    pub proxy: ProxyOptions,
+    pub resolve: Vec<ResolveOverride>,
+    pub connect_to: Vec<ConnectTo>,
+    #[cfg(unix)]
+    pub unix_socket: Option<PathBuf>, // replaces TCP, proxies and DNS
+    pub location: Option<FollowRedirects>, // redirects are not followed if not set
+    pub retry: RetryPolicy,
+    pub cookies: CookieOptions,
+    pub compressed: bool, // ask for and decode compressed body
+    pub raw: bool,        // don't decode `Content-Encoding`
+    pub fail: HttpFailMode,
+    pub select: Option<JsonSelector>,
+    pub raw_output: bool, // print selected strings without quotes
+    pub download: Option<HttpDownload>,
+    pub head_only: bool, // status line and headers are printed by `CurlautOutput::head`
+    pub write_out: Option<WriteOut>,
+    pub timing: bool,
+    pub auth_info: Option<AuthInfo>,
+    pub output_format: OutputFormat,
+}
+
+/// Limits of request phases, `None` for no limit.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct HttpTimeouts {
+    /// name lookup, TCP and TLS handshake
+    pub connect: Option<Duration>,
+    /// between two reads of the response, detects stalled connections
+    pub read: Option<Duration>,
+    /// the whole exchange including the body
+    pub total: Option<Duration>,
+}
+
+/// `-L` options.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FollowRedirects {
+    pub max_redirects: u32,
+    /// keep the token when redirected to another host
+    pub trusted: bool,
+}
+
+/// Save response body to a file instead of printing it.
+#[derive(Debug)]
+pub struct HttpDownload {
+    pub file: DownloadFile,
+    pub continue_at: Option<ContinueAt>,
+}
+
+#[derive(Debug)]
+pub enum DownloadFile {
+    Path(PathBuf),
+    /// from `Content-Disposition` or the last segment of URL path
+    RemoteName,
+}
+
+/// Resume interrupted download using `Range` header.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContinueAt {
+    Offset(u64),
+    /// size of already downloaded file
+    FileSize,
+}
+
+/// What to do when response status is 4xx or 5xx.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HttpFailMode {
+    /// print body and succeed
+    Never,
+    /// don't print body and fail
+    WithoutBody,
+    /// print body and fail
+    WithBody,
 }
 
 #[derive(Debug)]
@@ -29,7 +112,75 @@ impl Display for HttpRequestMethod {
     }
 }
 
-pub struct HttpRequestHeaders<'a>(pub HashMap<&'a str, &'a str>);
+/// Ordered headers, the same name may appear several times.
+/// `None` value removes the header added by default (e.g. `Authorization` or `Content-Type`).
+#[derive(Debug, Default)]
+pub struct HttpRequestHeaders(Vec<(HeaderName, Option<HeaderValue>)>);
+
+impl HttpRequestHeaders {
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Parses curl-like header syntax:
+    /// `Name: value` adds header, `Name:` removes header, `Name;` adds header with empty value.
+    pub fn add_parsed(&mut self, header: &str) -> Result<(), RequestError> {
+        if let Some((name, value)) = header.split_once(':') {
+            let value = value.trim();
+            if value.is_empty() {
+                return self.remove(name);
+            }
+            return self.append(name, value);
+        }
+        if let Some(name) = header.strip_suffix(';') {
+            return self.append(name, "");
+        }
+        Err(RequestError::InvalidHeader(header.to_owned()))
+    }
+
+    pub fn append(&mut self, name: &str, value: &str) -> Result<(), RequestError> {
+        let name = Self::parse_name(name)?;
+        let value = HeaderValue::from_str(value)
+            .map_err(|_| RequestError::InvalidHeaderValue(name.to_string()))?;
+        self.0.push((name, Some(value)));
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<(), RequestError> {
+        let name = Self::parse_name(name)?;
+        self.0.push((name, None));
+        Ok(())
+    }
+
+    /// Puts `defaults` before these headers, defaults with the same name are dropped.
+    pub fn with_defaults(self, defaults: HttpRequestHeaders) -> Self {
+        let mut merged: Vec<_> = defaults
+            .0
+            .into_iter()
+            .filter(|(name, _)| !self.0.iter().any(|(overridden, _)| overridden == name))
+            .collect();
+        merged.extend(self.0);
+        Self(merged)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(HeaderName, Option<HeaderValue>)> {
+        self.0.iter()
+    }
+
+    fn parse_name(name: &str) -> Result<HeaderName, RequestError> {
+        HeaderName::from_bytes(name.trim().as_bytes())
+            .map_err(|_| RequestError::InvalidHeaderName(name.trim().to_owned()))
+    }
+}
+
+impl IntoIterator for HttpRequestHeaders {
+    type Item = (HeaderName, Option<HeaderValue>);
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
 
 pub enum HttpRequestBody {
     Empty,
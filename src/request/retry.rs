@@ -0,0 +1,145 @@
+use crate::output::CurlautOutput;
+use reqwest::blocking::{Client, Request, Response};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::Method;
+use std::io::Write;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+pub const DEFAULT_RETRY_STATUSES: [u16; 6] = [408, 429, 500, 502, 503, 504];
+
+/// Backoff doesn't grow beyond it, a longer `Retry-After` isn't waited for.
+const MAX_BACKOFF: Duration = Duration::from_secs(600);
+
+/// `--retry` options, applied to the token and target requests.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// attempts after the first one, 0 to not retry
+    pub retries: u32,
+    /// before the first retry, doubled for every next one
+    pub delay: Duration,
+    /// no retry is started later than this since the first attempt
+    pub max_time: Option<Duration>,
+    pub statuses: Vec<u16>,
+    /// retry POST and PATCH even if they may have reached the server
+    pub all_methods: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 0,
+            delay: Duration::from_secs(1),
+            max_time: None,
+            statuses: DEFAULT_RETRY_STATUSES.to_vec(),
+            all_methods: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before the next attempt, `None` if the failed one must not be retried.
+    /// A request which couldn't connect is always safe to repeat, others only if idempotent.
+    fn next_delay(
+        &self,
+        attempt: u32,
+        elapsed: Duration,
+        idempotent: bool,
+        result: &reqwest::Result<Response>,
+    ) -> Option<Duration> {
+        if attempt > self.retries {
+            return None;
+        }
+        let repeatable = idempotent || self.all_methods;
+        let retry_after = match result {
+            Ok(response) => {
+                if !repeatable || !self.statuses.contains(&response.status().as_u16()) {
+                    return None;
+                }
+                retry_after(response.headers())
+            }
+            Err(err) if err.is_connect() => None,
+            Err(err) if repeatable && (err.is_timeout() || err.is_request()) => None,
+            Err(_) => return None,
+        };
+        let delay = retry_after.unwrap_or_else(|| self.backoff(attempt));
+        match self.max_time {
+            Some(max_time) if elapsed + delay > max_time => None,
+            _ => Some(delay),
+        }
+    }
+
+    /// Exponential, randomized between a half and the full delay to spread concurrent clients.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt - 1);
+        let delay = self.delay.saturating_mul(factor).min(MAX_BACKOFF);
+        delay.mul_f64(0.5 + fastrand::f64() / 2.0)
+    }
+}
+
+/// POST and PATCH may change the server state each time they are sent.
+pub fn is_idempotent(method: &Method) -> bool {
+    *method != Method::POST && *method != Method::PATCH
+}
+
+/// Sends the request until it succeeds or the policy gives up, the result of the last attempt
+/// is returned. Retries are logged to verbose output, failing to write it is the outer error.
+pub(crate) fn execute_with_retry(
+    client: &Client,
+    mut request: Request,
+    policy: &RetryPolicy,
+    idempotent: bool,
+    io: &mut impl CurlautOutput,
+) -> std::io::Result<reqwest::Result<Response>> {
+    let started = Instant::now();
+    let mut attempt = 1;
+    loop {
+        // bodies are always in memory, so the request can be cloned
+        let next_request = (policy.retries > 0).then(|| request.try_clone()).flatten();
+        let result = client.execute(request);
+        let next = next_request.and_then(|next_request| {
+            let delay = policy.next_delay(attempt, started.elapsed(), idempotent, &result)?;
+            Some((next_request, delay))
+        });
+        let Some((next_request, delay)) = next else {
+            return Ok(result);
+        };
+        let format_delay = |delay: Duration| {
+            humantime::format_duration(Duration::from_millis(delay.as_millis() as u64))
+        };
+        if delay > MAX_BACKOFF {
+            // only `Retry-After` may ask for it, the server isn't expected back soon
+            writeln!(
+                io.verbose(),
+                "* Retry-After of {} is longer than {}, giving up",
+                format_delay(delay),
+                format_delay(MAX_BACKOFF)
+            )?;
+            return Ok(result);
+        }
+        let problem = match &result {
+            Ok(response) => format!("HTTP status {}", response.status().as_u16()),
+            Err(err) if err.is_timeout() => "timeout".to_owned(),
+            Err(_) => "connection failure".to_owned(),
+        };
+        writeln!(
+            io.verbose(),
+            "* Problem: {problem}, will retry in {}, {} retries left",
+            format_delay(delay),
+            policy.retries - attempt + 1
+        )?;
+        thread::sleep(delay);
+        request = next_request;
+        attempt += 1;
+    }
+}
+
+/// Seconds or HTTP date, dates in the past mean now.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(date.duration_since(SystemTime::now()).unwrap_or_default())
+}
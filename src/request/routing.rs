@@ -0,0 +1,181 @@
+use crate::request::request_error::RequestError;
+use std::net::{IpAddr, ToSocketAddrs};
+use std::str::FromStr;
+use url::{Host, Url};
+
+/// `--resolve host:port:addr[,addr]`, connections to the host and port go to the addresses
+/// instead of the ones from DNS.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolveOverride {
+    host: String,
+    port: u16,
+    addrs: Vec<IpAddr>,
+}
+
+impl FromStr for ResolveOverride {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("expected 'host:port:addr[,addr]', got '{value}'");
+        let [host, port, addrs] = split_fields(value)[..] else {
+            return Err(invalid());
+        };
+        let port = port.parse().map_err(|_| invalid())?;
+        let addrs = addrs
+            .split(',')
+            .map(|addr| unbracket(addr).parse())
+            .collect::<Result<Vec<IpAddr>, _>>()
+            .map_err(|_| invalid())?;
+        if host.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Self {
+            host: unbracket(host).to_owned(),
+            port,
+            addrs,
+        })
+    }
+}
+
+/// `--connect-to host1:port1:host2:port2`, connections to host1 and port1 go to host2 and port2.
+/// Empty host1 or port1 matches any, empty host2 or port2 keeps the original one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConnectTo {
+    from_host: Option<String>,
+    from_port: Option<u16>,
+    to_host: Option<String>,
+    to_port: Option<u16>,
+}
+
+impl FromStr for ConnectTo {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("expected 'host1:port1:host2:port2', got '{value}'");
+        let [from_host, from_port, to_host, to_port] = split_fields(value)[..] else {
+            return Err(invalid());
+        };
+        let host = |host: &str| (!host.is_empty()).then(|| unbracket(host).to_owned());
+        let port = |port: &str| match port {
+            "" => Ok(None),
+            port => port.parse().map(Some).map_err(|_| invalid()),
+        };
+        Ok(Self {
+            from_host: host(from_host),
+            from_port: port(from_port)?,
+            to_host: host(to_host),
+            to_port: port(to_port)?,
+        })
+    }
+}
+
+impl ConnectTo {
+    fn matches(&self, host: &str, port: u16) -> bool {
+        self.from_host
+            .as_ref()
+            .is_none_or(|from_host| from_host.eq_ignore_ascii_case(host))
+            && self.from_port.is_none_or(|from_port| from_port == port)
+    }
+}
+
+/// Where the connection to the target actually goes.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Route {
+    pub host: String,
+    pub port: u16,
+    /// by `--connect-to` to another host or port
+    pub rerouted: bool,
+    /// `None` if the client resolves the URL host itself
+    pub addrs: Option<Vec<IpAddr>>,
+}
+
+/// The first matching `--connect-to` changes the host and port, then `--resolve` entry for them
+/// gives the addresses. A host changed by `--connect-to` is resolved here, because the client
+/// resolves only the URL host.
+pub(crate) fn route(
+    url: &Url,
+    resolve: &[ResolveOverride],
+    connect_to: &[ConnectTo],
+) -> Result<Route, RequestError> {
+    let no_host = || RequestError::NoHost(url.clone());
+    let url_host = match url.host().ok_or_else(no_host)? {
+        Host::Domain(domain) => domain.to_owned(),
+        Host::Ipv4(addr) => addr.to_string(),
+        Host::Ipv6(addr) => addr.to_string(),
+    };
+    let url_port = url.port_or_known_default().ok_or_else(no_host)?;
+    let connect = connect_to
+        .iter()
+        .find(|connect| connect.matches(&url_host, url_port));
+    let host = connect
+        .and_then(|connect| connect.to_host.clone())
+        .unwrap_or_else(|| url_host.clone());
+    let port = connect
+        .and_then(|connect| connect.to_port)
+        .unwrap_or(url_port);
+    let rerouted = !host.eq_ignore_ascii_case(&url_host) || port != url_port;
+    let addrs = match resolve
+        .iter()
+        .find(|entry| entry.host.eq_ignore_ascii_case(&host) && entry.port == port)
+    {
+        Some(entry) => Some(entry.addrs.clone()),
+        None if !host.eq_ignore_ascii_case(&url_host) => Some(lookup(&host, port)?),
+        None => None,
+    };
+    if addrs.is_some() && !matches!(url.host(), Some(Host::Domain(_))) {
+        return Err(RequestError::Reroute(url.clone()));
+    }
+    Ok(Route {
+        host,
+        port,
+        rerouted,
+        addrs,
+    })
+}
+
+/// Addresses of the host from DNS, the host may be an IP address itself.
+fn lookup(host: &str, port: u16) -> Result<Vec<IpAddr>, RequestError> {
+    if let Ok(addr) = host.parse() {
+        return Ok(vec![addr]);
+    }
+    let addrs = (host, port)
+        .to_socket_addrs()
+        .map_err(|source| RequestError::Resolve {
+            host: host.to_owned(),
+            source,
+        })?;
+    let mut ips: Vec<IpAddr> = Vec::new();
+    for addr in addrs {
+        if !ips.contains(&addr.ip()) {
+            ips.push(addr.ip());
+        }
+    }
+    Ok(ips)
+}
+
+/// Splits by ':' outside of brackets around IPv6 addresses.
+fn split_fields(value: &str) -> Vec<&str> {
+    let mut fields = Vec::new();
+    let mut start = 0;
+    let mut in_brackets = false;
+    for (index, char) in value.char_indices() {
+        match char {
+            '[' => in_brackets = true,
+            ']' => in_brackets = false,
+            ':' if !in_brackets => {
+                fields.push(&value[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    fields.push(&value[start..]);
+    fields
+}
+
+fn unbracket(value: &str) -> &str {
+    value
+        .strip_prefix('[')
+        .and_then(|value| value.strip_suffix(']'))
+        .unwrap_or(value)
+}
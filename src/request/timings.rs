@@ -0,0 +1,93 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Durations of request phases for `--write-out` and `--timing`, counted from the request start.
+/// Token acquisition happens before the request, so `auth` is measured separately.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct HttpTimings {
+    pub auth: Option<Duration>,
+    /// name lookup, TCP and TLS handshake are done
+    pub connect: Option<Duration>,
+    /// response status line and headers are received
+    pub start_transfer: Option<Duration>,
+    pub total: Duration,
+}
+
+/// Connector layer remembering when the first connection of the client was established.
+#[derive(Clone)]
+pub(crate) struct ConnectTimer {
+    started: Instant,
+    connected: Arc<Mutex<Option<Duration>>>,
+}
+
+impl ConnectTimer {
+    pub fn new(started: Instant) -> Self {
+        Self {
+            started,
+            connected: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn connected(&self) -> Option<Duration> {
+        *self
+            .connected
+            .lock()
+            .expect("connect timer lock is never poisoned")
+    }
+
+    fn record(&self) {
+        let mut connected = self
+            .connected
+            .lock()
+            .expect("connect timer lock is never poisoned");
+        connected.get_or_insert_with(|| self.started.elapsed());
+    }
+}
+
+impl<S> Layer<S> for ConnectTimer {
+    type Service = ConnectTimerService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ConnectTimerService {
+            inner,
+            timer: self.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct ConnectTimerService<S> {
+    inner: S,
+    timer: ConnectTimer,
+}
+
+impl<S, R> Service<R> for ConnectTimerService<S>
+where
+    S: Service<R>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: R) -> Self::Future {
+        let connecting = self.inner.call(request);
+        let timer = self.timer.clone();
+        Box::pin(async move {
+            let connection = connecting.await;
+            if connection.is_ok() {
+                timer.record();
+            }
+            connection
+        })
+    }
+}
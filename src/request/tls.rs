@@ -0,0 +1,524 @@
+use crate::request::request_error::RequestError;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::WebPkiServerVerifier;
+use rustls::crypto::cipher::{
+    AeadKey, Iv, KeyBlockShape, MessageDecrypter, MessageEncrypter, Tls12AeadAlgorithm,
+    Tls13AeadAlgorithm, UnsupportedOperationError,
+};
+use rustls::crypto::{CipherSuiteCommon, WebPkiSupportedAlgorithms};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::{
+    CertificateError, CipherSuite, ClientConfig, ConnectionTrafficSecrets, DigitallySignedStruct,
+    ProtocolVersion, RootCertStore, SignatureScheme, SupportedCipherSuite, Tls12CipherSuite,
+    Tls13CipherSuite,
+};
+use sha2::{Digest, Sha256};
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+/// TLS settings of an HTTP client.
+#[derive(Debug, Clone, Default)]
+pub struct TlsOptions {
+    pub insecure: bool,
+    /// PEM bundle trusted instead of system and Mozilla roots
+    pub ca_cert: Option<PathBuf>,
+    /// directory of PEM files trusted instead of system and Mozilla roots
+    pub ca_path: Option<PathBuf>,
+    pub client_cert: Option<ClientCertificate>,
+    /// the server key must match one of them if not empty
+    pub pinned_public_keys: Vec<PublicKeyPin>,
+}
+
+/// Certificate presented to servers requiring mutual TLS.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClientCertificate {
+    /// PEM certificate chain, with the key unless `key` is set, or PKCS#12 archive
+    pub cert: PathBuf,
+    pub key: Option<PathBuf>,
+    /// of PKCS#12 archive
+    pub password: Option<String>,
+}
+
+impl ClientCertificate {
+    /// `cert` is `file[:password]` like in curl, it's taken as a file if such file exists.
+    pub fn parse(cert: &str, key: Option<PathBuf>) -> Self {
+        let (cert, password) = match cert.rsplit_once(':') {
+            Some((file, password)) if !Path::new(cert).exists() => (file, Some(password)),
+            _ => (cert, None),
+        };
+        Self {
+            cert: PathBuf::from(cert),
+            key,
+            password: password.map(|password| password.to_owned()),
+        }
+    }
+
+    /// Reads PKCS#12 archive if the file has no PEM certificates.
+    fn load(&self) -> Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>), RequestError> {
+        let content = read_file(&self.cert)?;
+        let chain: Vec<_> = CertificateDer::pem_slice_iter(&content)
+            .collect::<Result<_, _>>()
+            .map_err(|err| invalid_certificate(&self.cert, err))?;
+        if chain.is_empty() {
+            return self.load_pkcs12(&content);
+        }
+        let key = match &self.key {
+            Some(key_path) => PrivateKeyDer::from_pem_slice(&read_file(key_path)?)
+                .map_err(|err| invalid_certificate(key_path, err))?,
+            None => PrivateKeyDer::from_pem_slice(&content)
+                .map_err(|err| invalid_certificate(&self.cert, err))?,
+        };
+        Ok((chain, key))
+    }
+
+    fn load_pkcs12(
+        &self,
+        content: &[u8],
+    ) -> Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>), RequestError> {
+        let password = self.password.as_deref().unwrap_or_default();
+        let key_store = p12_keystore::KeyStore::from_pkcs12(content, password)
+            .map_err(|err| invalid_certificate(&self.cert, err))?;
+        let (_, key_chain) = key_store
+            .private_key_chain()
+            .ok_or_else(|| invalid_certificate(&self.cert, "no private key found"))?;
+        let chain = key_chain
+            .chain()
+            .iter()
+            .map(|cert| CertificateDer::from(cert.as_der().to_vec()))
+            .collect();
+        let key = PrivateKeyDer::Pkcs8(key_chain.key().to_vec().into());
+        Ok((chain, key))
+    }
+}
+
+/// Protocol version and cipher suite chosen during the handshake.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TlsSession {
+    pub version: ProtocolVersion,
+    pub cipher_suite: CipherSuite,
+}
+
+impl Display for TlsSession {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let version = match self.version {
+            ProtocolVersion::TLSv1_2 => "TLSv1.2".to_owned(),
+            ProtocolVersion::TLSv1_3 => "TLSv1.3".to_owned(),
+            version => format!("{version:?}"),
+        };
+        write!(f, "{version} / {:?}", self.cipher_suite)
+    }
+}
+
+/// SHA-256 of the public key (SubjectPublicKeyInfo) of a certificate,
+/// written as `sha256//<base64>` like in curl's `--pinnedpubkey`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PublicKeyPin([u8; 32]);
+
+impl PublicKeyPin {
+    pub fn parse(pin: &str) -> Result<Self, RequestError> {
+        let invalid = || RequestError::InvalidPin(pin.to_owned());
+        let hash = pin.trim().strip_prefix("sha256//").ok_or_else(invalid)?;
+        let hash = BASE64.decode(hash).map_err(|_| invalid())?;
+        Ok(Self(hash.try_into().map_err(|_| invalid())?))
+    }
+
+    /// Pins separated by ';'.
+    pub fn parse_list(pins: &str) -> Result<Vec<Self>, RequestError> {
+        pins.split(';').map(Self::parse).collect()
+    }
+
+    fn of_certificate(certificate: &X509Certificate) -> Self {
+        Self(Sha256::digest(certificate.public_key().raw).into())
+    }
+}
+
+impl Display for PublicKeyPin {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "sha256//{}", BASE64.encode(self.0))
+    }
+}
+
+/// Subject, issuer, expiry and key pin of the server certificate for verbose output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CertificateSummary {
+    pub subject: String,
+    pub issuer: String,
+    pub not_after: String,
+    pub public_key_pin: PublicKeyPin,
+}
+
+impl CertificateSummary {
+    /// `None` if the certificate can't be parsed.
+    pub fn from_der(der: &[u8]) -> Option<CertificateSummary> {
+        let (_, certificate) = X509Certificate::from_der(der).ok()?;
+        Some(CertificateSummary {
+            subject: certificate.subject().to_string(),
+            issuer: certificate.issuer().to_string(),
+            not_after: certificate.validity().not_after.to_string(),
+            public_key_pin: PublicKeyPin::of_certificate(&certificate),
+        })
+    }
+}
+
+/// Remembers what happened during handshakes of one client's connections.
+/// Handshake errors reach the caller wrapped into `io::Error`, which hides their details.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TlsRecorder(Arc<Mutex<TlsRecord>>);
+
+#[derive(Debug, Default)]
+struct TlsRecord {
+    session: Option<TlsSession>,
+    pin_mismatch: Option<PublicKeyPin>,
+}
+
+impl TlsRecorder {
+    /// Of the last handshake, reqwest's `TlsInfo` has only the server certificate.
+    pub fn session(&self) -> Option<TlsSession> {
+        self.lock().session
+    }
+
+    /// Observed key of the server if it didn't match any pin.
+    pub fn pin_mismatch(&self) -> Option<PublicKeyPin> {
+        self.lock().pin_mismatch.clone()
+    }
+
+    fn record(&self, session: TlsSession) {
+        self.lock().session = Some(session);
+    }
+
+    fn record_pin_mismatch(&self, observed: PublicKeyPin) {
+        self.lock().pin_mismatch = Some(observed);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, TlsRecord> {
+        self.0.lock().expect("tls recorder lock is never poisoned")
+    }
+}
+
+/// Builds rustls config instead of reqwest to be able to see what was negotiated.
+pub(crate) fn client_config(
+    options: &TlsOptions,
+    http1: bool,
+    recorder: &TlsRecorder,
+) -> Result<ClientConfig, RequestError> {
+    let mut provider = rustls::crypto::ring::default_provider();
+    provider.cipher_suites = provider
+        .cipher_suites
+        .into_iter()
+        .map(|suite| record_suite(suite, recorder))
+        .collect();
+    let provider = Arc::new(provider);
+    let mut verifier: Arc<dyn ServerCertVerifier> = if options.insecure {
+        Arc::new(NoVerifier(provider.signature_verification_algorithms))
+    } else {
+        let roots = Arc::new(root_certificates(options)?);
+        WebPkiServerVerifier::builder_with_provider(roots, provider.clone())
+            .build()
+            .map_err(|err| RequestError::Tls(rustls::Error::General(err.to_string())))?
+    };
+    if !options.pinned_public_keys.is_empty() {
+        verifier = Arc::new(PinningVerifier {
+            inner: verifier,
+            pins: options.pinned_public_keys.clone(),
+            recorder: recorder.clone(),
+        });
+    }
+    let builder = ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(RequestError::Tls)?
+        .dangerous()
+        .with_custom_certificate_verifier(verifier);
+    let mut config = match &options.client_cert {
+        Some(client_cert) => {
+            let (chain, key) = client_cert.load()?;
+            builder
+                .with_client_auth_cert(chain, key)
+                .map_err(RequestError::Tls)?
+        }
+        None => builder.with_no_client_auth(),
+    };
+    // reqwest doesn't set ALPN for preconfigured TLS
+    config.alpn_protocols = if http1 {
+        vec![b"http/1.1".to_vec()]
+    } else {
+        vec![b"h2".to_vec(), b"http/1.1".to_vec()]
+    };
+    Ok(config)
+}
+
+/// `--cacert` and `--capath` replace Mozilla roots and the system store,
+/// which are used like reqwest does with `rustls-tls-native-roots`.
+fn root_certificates(options: &TlsOptions) -> Result<RootCertStore, RequestError> {
+    if options.ca_cert.is_none() && options.ca_path.is_none() {
+        let mut roots = RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        };
+        // native stores often contain certificates rustls can't parse, they are skipped
+        let native = rustls_native_certs::load_native_certs();
+        roots.add_parsable_certificates(native.certs);
+        return Ok(roots);
+    }
+    let mut roots = RootCertStore::empty();
+    if let Some(ca_cert) = &options.ca_cert {
+        let content = read_file(ca_cert)?;
+        for cert in CertificateDer::pem_slice_iter(&content) {
+            let cert = cert.map_err(|err| invalid_certificate(ca_cert, err))?;
+            roots
+                .add(cert)
+                .map_err(|err| invalid_certificate(ca_cert, err))?;
+        }
+        if roots.is_empty() {
+            return Err(invalid_certificate(ca_cert, "no PEM certificates found"));
+        }
+    }
+    if let Some(ca_path) = &options.ca_path {
+        let entries = fs::read_dir(ca_path).map_err(|source| RequestError::CertificateFile {
+            path: ca_path.clone(),
+            source,
+        })?;
+        for entry in entries {
+            let path = entry
+                .map_err(|source| RequestError::CertificateFile {
+                    path: ca_path.clone(),
+                    source,
+                })?
+                .path();
+            // the directory may have other files, like keys or hash links made by c_rehash
+            if let Ok(content) = fs::read(&path) {
+                let certs = CertificateDer::pem_slice_iter(&content).filter_map(Result::ok);
+                roots.add_parsable_certificates(certs);
+            }
+        }
+        if roots.is_empty() {
+            return Err(invalid_certificate(ca_path, "no PEM certificates found"));
+        }
+    }
+    Ok(roots)
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, RequestError> {
+    fs::read(path).map_err(|source| RequestError::CertificateFile {
+        path: path.to_owned(),
+        source,
+    })
+}
+
+fn invalid_certificate(path: &Path, reason: impl Display) -> RequestError {
+    RequestError::InvalidCertificate {
+        path: path.to_owned(),
+        reason: reason.to_string(),
+    }
+}
+
+/// Wraps the suite to record it when it's used for encryption, i.e. chosen by the server.
+/// Suites must be `'static` for rustls, so the few bytes of the wrappers of every client are
+/// leaked, a run builds at most two clients.
+fn record_suite(suite: SupportedCipherSuite, recorder: &TlsRecorder) -> SupportedCipherSuite {
+    match suite {
+        SupportedCipherSuite::Tls13(suite) => {
+            let aead_alg = Box::leak(Box::new(RecordingAead {
+                inner: suite.aead_alg,
+                session: session(ProtocolVersion::TLSv1_3, &suite.common),
+                recorder: recorder.clone(),
+            }));
+            SupportedCipherSuite::Tls13(Box::leak(Box::new(Tls13CipherSuite {
+                common: copy_common(&suite.common),
+                aead_alg,
+                ..*suite
+            })))
+        }
+        SupportedCipherSuite::Tls12(suite) => {
+            let aead_alg = Box::leak(Box::new(RecordingAead {
+                inner: suite.aead_alg,
+                session: session(ProtocolVersion::TLSv1_2, &suite.common),
+                recorder: recorder.clone(),
+            }));
+            SupportedCipherSuite::Tls12(Box::leak(Box::new(Tls12CipherSuite {
+                common: copy_common(&suite.common),
+                aead_alg,
+                ..*suite
+            })))
+        }
+    }
+}
+
+fn session(version: ProtocolVersion, common: &CipherSuiteCommon) -> TlsSession {
+    TlsSession {
+        version,
+        cipher_suite: common.suite,
+    }
+}
+
+fn copy_common(common: &CipherSuiteCommon) -> CipherSuiteCommon {
+    CipherSuiteCommon {
+        suite: common.suite,
+        hash_provider: common.hash_provider,
+        confidentiality_limit: common.confidentiality_limit,
+    }
+}
+
+struct RecordingAead<A: ?Sized + 'static> {
+    inner: &'static A,
+    session: TlsSession,
+    recorder: TlsRecorder,
+}
+
+impl<A: ?Sized> RecordingAead<A> {
+    fn record(&self) {
+        self.recorder.record(self.session);
+    }
+}
+
+impl Tls13AeadAlgorithm for RecordingAead<dyn Tls13AeadAlgorithm> {
+    fn encrypter(&self, key: AeadKey, iv: Iv) -> Box<dyn MessageEncrypter> {
+        self.record();
+        self.inner.encrypter(key, iv)
+    }
+
+    fn decrypter(&self, key: AeadKey, iv: Iv) -> Box<dyn MessageDecrypter> {
+        self.inner.decrypter(key, iv)
+    }
+
+    fn key_len(&self) -> usize {
+        self.inner.key_len()
+    }
+
+    fn extract_keys(
+        &self,
+        key: AeadKey,
+        iv: Iv,
+    ) -> Result<ConnectionTrafficSecrets, UnsupportedOperationError> {
+        self.inner.extract_keys(key, iv)
+    }
+
+    fn fips(&self) -> bool {
+        self.inner.fips()
+    }
+}
+
+impl Tls12AeadAlgorithm for RecordingAead<dyn Tls12AeadAlgorithm> {
+    fn encrypter(&self, key: AeadKey, iv: &[u8], extra: &[u8]) -> Box<dyn MessageEncrypter> {
+        self.record();
+        self.inner.encrypter(key, iv, extra)
+    }
+
+    fn decrypter(&self, key: AeadKey, iv: &[u8]) -> Box<dyn MessageDecrypter> {
+        self.inner.decrypter(key, iv)
+    }
+
+    fn key_block_shape(&self) -> KeyBlockShape {
+        self.inner.key_block_shape()
+    }
+
+    fn extract_keys(
+        &self,
+        key: AeadKey,
+        iv: &[u8],
+        explicit: &[u8],
+    ) -> Result<ConnectionTrafficSecrets, UnsupportedOperationError> {
+        self.inner.extract_keys(key, iv, explicit)
+    }
+
+    fn fips(&self) -> bool {
+        self.inner.fips()
+    }
+}
+
+/// Checks the key of the server after the usual verification, also with `--insecure` like curl.
+#[derive(Debug)]
+struct PinningVerifier {
+    inner: Arc<dyn ServerCertVerifier>,
+    pins: Vec<PublicKeyPin>,
+    recorder: TlsRecorder,
+}
+
+impl ServerCertVerifier for PinningVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let verified =
+            self.inner
+                .verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)?;
+        let (_, certificate) = X509Certificate::from_der(end_entity)
+            .map_err(|_| rustls::Error::InvalidCertificate(CertificateError::BadEncoding))?;
+        let observed = PublicKeyPin::of_certificate(&certificate);
+        if !self.pins.contains(&observed) {
+            let message = format!("public key {observed} is not pinned");
+            self.recorder.record_pin_mismatch(observed);
+            return Err(rustls::Error::General(message));
+        }
+        Ok(verified)
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+/// `--insecure`: accepts any certificate and handshake signature.
+#[derive(Debug)]
+struct NoVerifier(WebPkiSupportedAlgorithms);
+
+impl ServerCertVerifier for NoVerifier {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        _message: &[u8],
+        _cert: &CertificateDer<'_>,
+        _dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        Ok(HandshakeSignatureValid::assertion())
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        _message: &[u8],
+        _cert: &CertificateDer<'_>,
+        _dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        Ok(HandshakeSignatureValid::assertion())
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.supported_schemes()
+    }
+}
@@ -1,6 +1,26 @@
 use assert_cmd::Command;
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use httpmock::{Method, MockServer};
-use predicates::prelude::predicate;
+use predicates::prelude::{predicate, PredicateBooleanExt};
+use serde_json::json;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{RootCertStore, ServerConfig, ServerConnection, StreamOwned};
+use std::fs;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+#[cfg(unix)]
+use std::os::unix::net::UnixListener;
+use std::path::PathBuf;
+#[cfg(unix)]
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
 
 #[test]
 fn cli_tests() {
@@ -49,6 +69,71 @@ fn http_get_verbose() {
         ));
 }
 
+#[test]
+fn http_get_verbose_protocol_and_received_bytes() {
+    // given
+    let mock_server = MockServer::start();
+    mock_server.mock(|when, then| {
+        when.path("/api/v1/get").method(Method::GET);
+        then.status(200).body("hello world");
+    });
+    let url = mock_server.url("/api/v1/get");
+    // when/then
+    get_cmd()
+        .args(vec!["GET", url.as_str(), "-v", "--http1"])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .success()
+        .stdout("hello world")
+        .stderr(predicate::str::contains(format!("> GET {url} HTTP/1.1\n")))
+        .stderr(predicate::str::contains("{ [11 bytes data]"))
+        .stderr(predicate::str::contains("SSL connection").not());
+}
+
+#[test]
+fn http_get_verbose_received_bytes_of_chunked_body() {
+    // given
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        read_request_head(&mut stream);
+        let response = "HTTP/1.1 200 OK\r\ntransfer-encoding: chunked\r\nconnection: close\r\n\r\n\
+                        5\r\nhello\r\n6\r\n world\r\n0\r\n\r\n";
+        stream.write_all(response.as_bytes()).unwrap();
+    });
+    // when/then
+    get_cmd()
+        .args(vec!["GET", &format!("http://127.0.0.1:{port}/api/v1/get"), "-v"])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .success()
+        .stdout("hello world")
+        .stderr(predicate::str::contains(r#"< "transfer-encoding": "chunked""#))
+        .stderr(predicate::str::contains("{ [11 bytes data]"));
+}
+
+#[test]
+fn http_get_verbose_http2_and_server_certificate() {
+    // given
+    let port = start_h2_server();
+    let url = format!("https://localhost:{port}/api/v1/get");
+    // when/then
+    get_cmd()
+        .args(vec!["GET", &url, "--cacert", tls_fixture("ca.pem").to_str().unwrap(), "-v"])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .success()
+        .stdout("ok")
+        .stderr(predicate::str::contains("* SSL connection using TLSv1.3 / TLS13_"))
+        .stderr(predicate::str::contains("* ALPN: using h2\n"))
+        .stderr(predicate::str::contains("*  subject: CN=localhost\n"))
+        .stderr(predicate::str::contains("*  expire date: Sep 25 00:27:17 2126 +00:00\n"))
+        .stderr(predicate::str::contains(format!("> GET {url} HTTP/2.0\n")))
+        .stderr(predicate::str::contains("< HTTP/2.0 200 OK"))
+        .stderr(predicate::str::contains("{ [2 bytes data]"));
+}
+
 #[test]
 fn http_post_verbose() {
     // given
@@ -120,6 +205,2044 @@ fn http_delete_verbose() {
         ));
 }
 
+#[test]
+fn http_post_request_items() {
+    // given
+    let mock_server = MockServer::start();
+    let mock = mock_server.mock(|when, then| {
+        when.path("/api/v1/post")
+            .method(Method::POST)
+            .query_param("search", "a b")
+            .header("x-custom", "custom value")
+            .json_body(json!({
+                "name": "curlaut",
+                "count": 1,
+                "nested": {"key": {"list": ["a", "b"]}},
+                "tags": ["z", "y"],
+            }));
+        then.status(201).body("ok");
+    });
+    // when/then
+    get_cmd()
+        .args(vec![
+            "POST",
+            mock_server.url("/api/v1/post").as_str(),
+            "name=curlaut",
+            "count:=1",
+            "nested[key][list][]=a",
+            "nested[key][list][]=b",
+            "tags[0]=x",
+            "tags[1]=y",
+            "tags[0]=z",
+            "search==a b",
+            "X-Custom: custom value",
+        ])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .success()
+        .stdout("ok");
+    mock.assert();
+}
+
+#[test]
+fn http_post_request_items_conflict_with_json_body() {
+    get_cmd()
+        .args(vec![
+            "POST",
+            "http://localhost/api/v1/post",
+            "name=curlaut",
+            "--json-body",
+            "{}",
+        ])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("can't be combined with --json-body"));
+}
+
+#[test]
+fn http_post_request_items_index_beyond_array_end() {
+    for index in ["18446744073709551615", "4000000000"] {
+        get_cmd()
+            .args(vec!["POST", "http://127.0.0.1:1/api/v1/post", "a[0]=x"])
+            .arg(format!("a[{index}]=1"))
+            .env("HOME", "tests/cmd/config-list/fs")
+            .assert()
+            .code(1)
+            .stderr(predicate::str::contains(format!(
+                "Conflicting request item path 'a[{index}]'"
+            )));
+    }
+}
+
+#[test]
+fn http_post_request_items_path_without_top_level_key() {
+    get_cmd()
+        .args(vec!["POST", "http://127.0.0.1:1/api/v1/post", "[x]=1"])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("Invalid request item path '[x]'"));
+}
+
+#[test]
+fn http_get_query_params() {
+    // given
+    let mock_server = MockServer::start();
+    let mock = mock_server.mock(|when, then| {
+        when.path("/api/v1/get")
+            .method(Method::GET)
+            .query_param("filter", "a&b=c")
+            .query_param("page", "2")
+            .query_param("tag", "x y");
+        then.status(200).body("ok");
+    });
+    // when/then
+    get_cmd()
+        .args(vec![
+            "GET",
+            mock_server.url("/api/v1/get").as_str(),
+            "-q",
+            "filter=a&b=c",
+            "--query",
+            "tag=x y",
+            "--get",
+            "page:=2",
+        ])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .success()
+        .stdout("ok");
+    mock.assert();
+}
+
+#[test]
+fn http_post_headers() {
+    // given
+    let mock_server = MockServer::start();
+    let mock = mock_server.mock(|when, then| {
+        when.path("/api/v1/post")
+            .method(Method::POST)
+            .header("content-type", "application/merge-patch+json")
+            .header("x-empty", "")
+            .header("x-trimmed", "value")
+            .matches(|req| {
+                let headers = req.headers.as_deref().unwrap_or_default();
+                !headers.iter().any(|(name, _)| name == "authorization")
+            });
+        then.status(200).body("ok");
+    });
+    // when/then
+    get_cmd()
+        .args(vec![
+            "POST",
+            mock_server.url("/api/v1/post").as_str(),
+            "--json-body",
+            "{}",
+            "-H",
+            "Content-Type: application/merge-patch+json",
+            "-H",
+            "X-Empty;",
+            "-H",
+            "X-Trimmed:   value  ",
+            "-H",
+            "Authorization:",
+        ])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .success()
+        .stdout("ok");
+    mock.assert();
+}
+
+#[test]
+fn http_get_repeated_headers() {
+    // given
+    let mock_server = MockServer::start();
+    mock_server.mock(|when, then| {
+        when.path("/api/v1/get").method(Method::GET);
+        then.status(200).body("ok");
+    });
+    // when/then
+    get_cmd()
+        .args(vec![
+            "GET",
+            mock_server.url("/api/v1/get").as_str(),
+            "-v",
+            "-H",
+            "Cookie: a=1",
+            "-H",
+            "Cookie: b=2",
+        ])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "> \"cookie\": \"a=1\"\n> \"cookie\": \"b=2\"",
+        ));
+}
+
+#[test]
+fn http_get_invalid_header() {
+    get_cmd()
+        .args(vec!["GET", "http://localhost/api/v1/get", "-H", "Bad Name: value"])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid header name 'Bad Name'"));
+}
+
+#[test]
+fn http_get_header_without_colon() {
+    get_cmd()
+        .args(vec!["GET", "http://localhost/api/v1/get", "-H", "X-Header"])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid header 'X-Header'"));
+}
+
+#[test]
+fn http_get_relative_url_with_default_headers() {
+    // given
+    let mock_server = MockServer::start();
+    let mock = mock_server.mock(|when, then| {
+        when.path("/api/orders/1")
+            .method(Method::GET)
+            .header("x-tenant-id", "42")
+            .header("accept", "application/vnd.company+json");
+        then.status(200).body("ok");
+    });
+    let home = home_with_config(
+        "relative-url",
+        &format!(
+            r#"
+- alias: local
+  url: http://localhost:8089/
+  realm: dlobanov
+  clientId: dlobanov_client
+  clientSecret: ''
+  username: user
+  password: password
+  default: true
+  baseUrl: {}
+  defaultHeaders:
+  - 'X-Tenant-Id: 42'
+  - 'Accept: application/json'
+"#,
+            mock_server.url("/api/")
+        ),
+    );
+    // when/then
+    get_cmd()
+        .args(vec![
+            "GET",
+            "/orders/1",
+            "-H",
+            "Accept: application/vnd.company+json",
+        ])
+        .env("HOME", home)
+        .assert()
+        .success()
+        .stdout("ok");
+    mock.assert();
+}
+
+const ROUTED_CONFIG: &str = r#"
+- alias: prod
+  url: http://localhost:8089/
+  realm: prod-realm
+  clientId: dlobanov_client
+  clientSecret: ''
+  username: user
+  password: password
+  default: true
+  routes:
+  - '*.prod.internal'
+- alias: local
+  url: http://localhost:8089/
+  realm: local-realm
+  clientId: dlobanov_client
+  clientSecret: ''
+  username: user
+  password: password
+  default: false
+  routes:
+  - 127.0.0.1
+"#;
+
+#[test]
+fn http_get_keycloak_chosen_by_route() {
+    // given
+    let mock_server = MockServer::start();
+    mock_server.mock(|when, then| {
+        when.path("/api/v1/get").method(Method::GET);
+        then.status(200).body("ok");
+    });
+    let home = home_with_config("chosen-by-route", ROUTED_CONFIG);
+    // when/then
+    get_cmd()
+        .args(vec!["GET", mock_server.url("/api/v1/get").as_str(), "-v"])
+        .env("HOME", home)
+        .assert()
+        .success()
+        .stdout("ok")
+        .stderr(predicate::str::contains("/realms/local-realm/"));
+}
+
+#[test]
+fn http_get_token_not_sent_to_unmatched_host() {
+    // given
+    let mock_server = MockServer::start();
+    let mock = mock_server.mock(|when, then| {
+        when.path("/api/v1/get").method(Method::GET);
+        then.status(200).body("ok");
+    });
+    let home = home_with_config("unmatched-host", ROUTED_CONFIG);
+    // when/then
+    get_cmd()
+        .args(vec![
+            "GET",
+            mock_server.url("/api/v1/get").as_str(),
+            "--auth",
+            "prod",
+        ])
+        .env("HOME", home)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Keycloak 'prod' is not allowed for",
+        ));
+    mock.assert_hits(0);
+}
+
+#[test]
+fn http_get_error_status_without_fail() {
+    // given
+    let mock_server = MockServer::start();
+    mock_server.mock(|when, then| {
+        when.path("/api/v1/get").method(Method::GET);
+        then.status(500).body("error");
+    });
+    // when/then
+    get_cmd()
+        .args(vec!["GET", mock_server.url("/api/v1/get").as_str()])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .success()
+        .stdout("error");
+}
+
+#[test]
+fn http_get_fail_on_server_error() {
+    // given
+    let mock_server = MockServer::start();
+    mock_server.mock(|when, then| {
+        when.path("/api/v1/get").method(Method::GET);
+        then.status(503).body("error");
+    });
+    // when/then
+    get_cmd()
+        .args(vec!["GET", mock_server.url("/api/v1/get").as_str(), "--fail"])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .code(7)
+        .stdout("");
+}
+
+#[test]
+fn http_get_fail_with_body_on_client_error() {
+    // given
+    let mock_server = MockServer::start();
+    mock_server.mock(|when, then| {
+        when.path("/api/v1/get").method(Method::GET);
+        then.status(404).body("not found");
+    });
+    // when/then
+    get_cmd()
+        .args(vec![
+            "GET",
+            mock_server.url("/api/v1/get").as_str(),
+            "--fail-with-body",
+        ])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .code(6)
+        .stdout("not found");
+}
+
+#[test]
+fn http_get_connection_failure() {
+    get_cmd()
+        .args(vec!["GET", "http://127.0.0.1:1/api/v1/get"])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .code(4);
+}
+
+#[test]
+fn http_get_auth_failure() {
+    let home = home_with_config(
+        "auth-failure",
+        r#"
+- alias: unreachable
+  url: http://127.0.0.1:1/
+  realm: dlobanov
+  clientId: dlobanov_client
+  clientSecret: ''
+  username: user
+  password: password
+  default: true
+"#,
+    );
+    get_cmd()
+        .args(vec!["GET", "http://127.0.0.1:1/api/v1/get"])
+        .env("HOME", home)
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains(
+            "Keycloak 'unreachable' is unreachable at",
+        ))
+        .stderr(predicate::str::contains("Caused by").not());
+}
+
+#[test]
+fn http_get_auth_invalid_grant() {
+    // given
+    let keycloak = MockServer::start();
+    keycloak.mock(|when, then| {
+        when.path("/realms/dlobanov/protocol/openid-connect/token")
+            .method(Method::POST);
+        then.status(401).json_body(json!({
+            "error": "invalid_grant",
+            "error_description": "Invalid user credentials",
+        }));
+    });
+    let home = home_with_config(
+        "auth-invalid-grant",
+        &format!(
+            r#"
+- alias: local
+  url: {}
+  realm: dlobanov
+  clientId: dlobanov_client
+  clientSecret: ''
+  username: user
+  password: wrong
+  default: true
+"#,
+            keycloak.base_url()
+        ),
+    );
+    // when/then
+    get_cmd()
+        .args(vec!["GET", "http://127.0.0.1:1/api/v1/get", "-v"])
+        .env("HOME", home)
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains(
+            "Keycloak 'local' rejected credentials: Invalid user credentials",
+        ));
+}
+
+#[test]
+fn http_get_connection_failure_verbose_error() {
+    get_cmd()
+        .args(vec!["GET", "http://127.0.0.1:1/api/v1/get", "-v"])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .code(4)
+        .stderr(predicate::str::contains(
+            "Failed to connect to http://127.0.0.1:1/api/v1/get",
+        ))
+        .stderr(predicate::str::contains("> GET http://127.0.0.1:1/api/v1/get\n"))
+        .stderr(predicate::str::contains("Caused by"));
+}
+
+#[test]
+fn http_get_json_raw_when_piped() {
+    // given
+    let mock_server = MockServer::start();
+    mock_server.mock(|when, then| {
+        when.path("/api/v1/get").method(Method::GET);
+        then.status(200)
+            .header("content-type", "application/json")
+            .body(r#"{"b":1,"a":[true,null]}"#);
+    });
+    // when/then
+    get_cmd()
+        .args(vec!["GET", mock_server.url("/api/v1/get").as_str()])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .success()
+        .stdout(r#"{"b":1,"a":[true,null]}"#);
+}
+
+#[test]
+fn http_get_json_pretty_format() {
+    // given
+    let mock_server = MockServer::start();
+    mock_server.mock(|when, then| {
+        when.path("/api/v1/get").method(Method::GET);
+        then.status(200)
+            .header("content-type", "application/problem+json; charset=utf-8")
+            .body(r#"{"b":1,"a":[true,null]}"#);
+    });
+    // when/then
+    get_cmd()
+        .args(vec![
+            "GET",
+            mock_server.url("/api/v1/get").as_str(),
+            "--pretty",
+            "format",
+            "-v",
+        ])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .success()
+        .stdout("{\n  \"b\": 1,\n  \"a\": [\n    true,\n    null\n  ]\n}\n")
+        .stderr(predicate::str::contains(
+            "< content-type: application/problem+json; charset=utf-8",
+        ));
+}
+
+#[test]
+fn http_get_json_pretty_colors() {
+    // given
+    let mock_server = MockServer::start();
+    mock_server.mock(|when, then| {
+        when.path("/api/v1/get").method(Method::GET);
+        then.status(200)
+            .header("content-type", "application/json")
+            .body(r#"{"key":"value"}"#);
+    });
+    // when/then
+    get_cmd()
+        .args(vec![
+            "GET",
+            mock_server.url("/api/v1/get").as_str(),
+            "--pretty",
+            "colors",
+        ])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .success()
+        .stdout("{\x1b[34;1m\"key\"\x1b[0m:\x1b[32m\"value\"\x1b[0m}");
+}
+
+#[test]
+fn http_get_not_json_not_formatted() {
+    // given
+    let mock_server = MockServer::start();
+    mock_server.mock(|when, then| {
+        when.path("/api/v1/get").method(Method::GET);
+        then.status(200)
+            .header("content-type", "text/plain")
+            .body(r#"{"key":"value"}"#);
+    });
+    // when/then
+    get_cmd()
+        .args(vec![
+            "GET",
+            mock_server.url("/api/v1/get").as_str(),
+            "--pretty",
+            "all",
+        ])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .success()
+        .stdout(r#"{"key":"value"}"#);
+}
+
+#[test]
+fn http_get_body_in_declared_charset() {
+    // given
+    let mock_server = MockServer::start();
+    mock_server.mock(|when, then| {
+        when.path("/api/v1/text").method(Method::GET);
+        then.status(200)
+            .header("content-type", "text/plain; charset=iso-8859-1")
+            .body(b"caf\xe9");
+    });
+    mock_server.mock(|when, then| {
+        when.path("/api/v1/json").method(Method::GET);
+        then.status(200)
+            .header("content-type", "application/json; charset=iso-8859-1")
+            .body(b"{\"name\":\"caf\xe9\"}");
+    });
+    // when/then
+    get_cmd()
+        .args(vec!["GET", mock_server.url("/api/v1/text").as_str()])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .success()
+        .stdout(b"caf\xe9".to_vec());
+    get_cmd()
+        .args(vec!["GET", mock_server.url("/api/v1/json").as_str(), "--select", ".name", "-r"])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .success()
+        .stdout("café\n");
+}
+
+fn items_mock_server() -> MockServer {
+    let mock_server = MockServer::start();
+    mock_server.mock(|when, then| {
+        when.path("/api/v1/items").method(Method::GET);
+        then.status(200).json_body(json!({
+            "items": [
+                {"id": 1, "name": "first"},
+                {"id": 2, "name": "second"},
+                {"id": 3, "name": "third"},
+            ],
+        }));
+    });
+    mock_server
+}
+
+#[test]
+fn http_get_select() {
+    let mock_server = items_mock_server();
+    let url = mock_server.url("/api/v1/items");
+    for (expr, raw_output, expected) in [
+        (".items[].id", false, "1\n2\n3\n"),
+        (".items[-1].name", false, "\"third\"\n"),
+        ("$.items[*].name", true, "first\nsecond\nthird\n"),
+        (".items[1:] | length", false, "2\n"),
+        (".items[0] | keys", false, "[\"id\",\"name\"]\n"),
+        (".[\"items\"][0].missing", false, "null\n"),
+    ] {
+        let mut args = vec!["GET", url.as_str(), "--select", expr];
+        if raw_output {
+            args.push("--raw-output");
+        }
+        get_cmd()
+            .args(args)
+            .env("HOME", "tests/cmd/config-list/fs")
+            .assert()
+            .success()
+            .stdout(expected);
+    }
+}
+
+#[test]
+fn http_get_select_quoted_key_with_pipe() {
+    // given
+    let mock_server = MockServer::start();
+    mock_server.mock(|when, then| {
+        when.path("/api/v1/items").method(Method::GET);
+        then.status(200)
+            .header("content-type", "application/json")
+            .body(r#"{"a|b":"pipe","x":{"c|d":[1,2]}}"#);
+    });
+    let url = mock_server.url("/api/v1/items");
+    // when/then
+    for (expr, expected) in [
+        (r#"."a|b""#, "\"pipe\"\n"),
+        (r#".["a|b"]"#, "\"pipe\"\n"),
+        (r#".x["c|d"] | length"#, "2\n"),
+    ] {
+        get_cmd()
+            .args(vec!["GET", url.as_str(), "--select", expr])
+            .env("HOME", "tests/cmd/config-list/fs")
+            .assert()
+            .success()
+            .stdout(expected);
+    }
+}
+
+#[test]
+fn http_get_select_invalid_expression() {
+    get_cmd()
+        .args(vec!["GET", "http://localhost/api/v1/items", "--select", "items"])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "Invalid select expression 'items': filter must start with '.'",
+        ));
+}
+
+#[test]
+fn http_get_invalid_arguments_reported_before_token_request() {
+    // given
+    let home = home_with_config(
+        "invalid-arguments-unreachable-keycloak",
+        r#"
+- alias: down
+  url: http://127.0.0.1:1/
+  realm: dlobanov
+  clientId: dlobanov_client
+  clientSecret: ''
+  username: user
+  password: password
+  default: true
+"#,
+    );
+    let cases = [
+        (vec!["--select", "items"], "Invalid select expression 'items'"),
+        (vec!["-w", "%{unknown}"], "Unknown write-out variable '%{unknown}'"),
+        (vec!["[x]=1"], "Invalid request item path '[x]'"),
+    ];
+    // when/then
+    for (args, error) in cases {
+        get_cmd()
+            .args(vec!["GET", "http://localhost/api/v1/items"])
+            .args(args)
+            .env("HOME", &home)
+            .assert()
+            .code(1)
+            .stderr(predicate::str::contains(error))
+            .stderr(predicate::str::contains("unreachable").not());
+    }
+}
+
+#[test]
+fn http_get_output_to_file() {
+    // given
+    let body: Vec<u8> = (0..=255).collect();
+    let mock_server = MockServer::start();
+    mock_server.mock(|when, then| {
+        when.path("/api/v1/export").method(Method::GET);
+        then.status(200).body(&body);
+    });
+    let dir = test_dir("output-to-file");
+    let file = dir.join("export.bin");
+    // when/then
+    get_cmd()
+        .args(vec!["GET", mock_server.url("/api/v1/export").as_str(), "-o"])
+        .arg(&file)
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .success()
+        .stdout("");
+    assert_eq!(fs::read(file).unwrap(), body);
+}
+
+#[test]
+fn http_get_remote_name_from_content_disposition() {
+    // given
+    let mock_server = MockServer::start();
+    mock_server.mock(|when, then| {
+        when.path("/api/v1/export").method(Method::GET);
+        then.status(200)
+            .header(
+                "content-disposition",
+                r#"attachment; filename="../ignored.csv"; filename*=UTF-8''report%202025.csv"#,
+            )
+            .body("a,b");
+    });
+    let dir = test_dir("remote-name");
+    // when/then
+    get_cmd()
+        .args(vec!["GET", mock_server.url("/api/v1/export").as_str(), "-O"])
+        .current_dir(&dir)
+        .env("HOME", home_fixture())
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(dir.join("report 2025.csv")).unwrap(), "a,b");
+}
+
+#[test]
+fn http_get_continue_download() {
+    // given
+    let mock_server = MockServer::start();
+    let mock = mock_server.mock(|when, then| {
+        when.path("/api/v1/data.txt")
+            .method(Method::GET)
+            .header("range", "bytes=6-");
+        then.status(206).body("world");
+    });
+    let dir = test_dir("continue-download");
+    fs::write(dir.join("data.txt"), "hello ").unwrap();
+    // when/then
+    get_cmd()
+        .args(vec![
+            "GET",
+            mock_server.url("/api/v1/data.txt").as_str(),
+            "-O",
+            "-C",
+            "-",
+        ])
+        .current_dir(&dir)
+        .env("HOME", home_fixture())
+        .assert()
+        .success();
+    mock.assert();
+    assert_eq!(
+        fs::read_to_string(dir.join("data.txt")).unwrap(),
+        "hello world"
+    );
+}
+
+#[test]
+fn http_get_include_and_dump_header() {
+    // given
+    let mock_server = MockServer::start();
+    mock_server.mock(|when, then| {
+        when.path("/api/v1/items").method(Method::GET);
+        then.status(201).header("x-request-id", "42").body("created");
+    });
+    let dir = test_dir("dump-header");
+    let file = dir.join("headers.txt");
+    // when/then
+    get_cmd()
+        .args(vec!["GET", mock_server.url("/api/v1/items").as_str(), "-i", "-D"])
+        .arg(&file)
+        .env("HOME", home_fixture())
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::starts_with("HTTP/1.1 201 Created\r\n")
+                .and(predicate::str::contains("x-request-id: 42\r\n"))
+                .and(predicate::str::ends_with("\r\n\r\ncreated")),
+        );
+    let dumped = fs::read_to_string(file).unwrap();
+    assert!(dumped.starts_with("HTTP/1.1 201 Created\r\n"));
+    assert!(dumped.ends_with("\r\n\r\n"));
+}
+
+#[test]
+fn http_get_head_only() {
+    let mock_server = items_mock_server();
+    get_cmd()
+        .args(vec!["GET", mock_server.url("/api/v1/items").as_str(), "-I"])
+        .env("HOME", home_fixture())
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::starts_with("HTTP/1.1 200 OK\r\n")
+                .and(predicate::str::contains("content-length: 84\r\n"))
+                .and(predicate::str::ends_with("\r\n\r\n")),
+        );
+}
+
+#[test]
+fn http_get_write_out_and_timing() {
+    // given
+    let mock_server = MockServer::start();
+    mock_server.mock(|when, then| {
+        when.path("/api/v1/items").method(Method::GET);
+        then.status(200)
+            .header("content-type", "text/plain")
+            .body("hello");
+    });
+    let url = mock_server.url("/api/v1/items");
+    // when/then
+    get_cmd()
+        .args(vec![
+            "GET",
+            url.as_str(),
+            "-w",
+            "\\n%{http_code} %{content_type} %{size_download} %{url_effective} %%",
+            "--timing",
+        ])
+        .env("HOME", home_fixture())
+        .assert()
+        .success()
+        .stdout(format!("hello\n200 text/plain 5 {url} %"))
+        .stderr(
+            predicate::str::is_match(r"(?m)^auth\s+\d+\.\d{3} ms$")
+                .unwrap()
+                .and(predicate::str::is_match(r"(?m)^connect\s+\d+\.\d{3} ms$").unwrap())
+                .and(predicate::str::is_match(r"(?m)^total\s+\d+\.\d{3} ms$").unwrap()),
+        );
+}
+
+#[test]
+fn http_get_write_out_unknown_variable() {
+    get_cmd()
+        .args(vec!["GET", "http://localhost/api/v1/items", "-w", "%{speed}"])
+        .env("HOME", home_fixture())
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "Unknown write-out variable '%{speed}'",
+        ));
+}
+
+#[test]
+fn http_get_output_format_json_conflicts_with_body_handling() {
+    let conflicting: [&[&str]; 6] = [
+        &["-o", "body.json"],
+        &["-O"],
+        &["--continue-at", "10"],
+        &["--select", ".id"],
+        &["--fail"],
+        &["--fail-with-body"],
+    ];
+    for args in conflicting {
+        get_cmd()
+            .args(vec!["GET", "http://localhost/api/v1/items", "--output-format", "json"])
+            .args(args)
+            .env("HOME", home_fixture())
+            .assert()
+            .code(2)
+            .stderr(predicate::str::contains("cannot be used with"));
+    }
+}
+
+#[test]
+fn http_post_output_format_json() {
+    // given
+    let mock_server = MockServer::start();
+    mock_server.mock(|when, then| {
+        when.path("/api/v1/items").method(Method::POST);
+        then.status(201)
+            .header("content-type", "application/json")
+            .body(r#"{"id":1}"#);
+    });
+    let url = mock_server.url("/api/v1/items");
+    // when
+    let output = get_cmd()
+        .args(vec!["POST", url.as_str(), "name=first", "--output-format", "json"])
+        .env("HOME", home_fixture())
+        .output()
+        .unwrap();
+    // then
+    assert!(output.status.success());
+    let envelope: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(envelope["request"]["method"], "POST");
+    assert_eq!(envelope["request"]["url"], url.as_str());
+    assert_eq!(envelope["request"]["headers"]["authorization"], "<redacted>");
+    assert_eq!(envelope["response"]["status"], 201);
+    assert_eq!(envelope["response"]["headers"]["content-type"], "application/json");
+    assert_eq!(envelope["response"]["body"], json!(null));
+    assert_eq!(envelope["response"]["body_json"], json!({"id": 1}));
+    assert!(envelope["timings"]["total"].is_f64());
+    assert_eq!(
+        envelope["auth"],
+        json!({"alias": "local", "token_source": "password_grant"})
+    );
+}
+
+#[test]
+fn http_get_output_format_json_binary_body() {
+    // given
+    let mock_server = MockServer::start();
+    mock_server.mock(|when, then| {
+        when.path("/api/v1/export").method(Method::GET);
+        then.status(200).body([0xff, 0x00, 0x01]);
+    });
+    // when
+    let output = get_cmd()
+        .args(vec![
+            "GET",
+            mock_server.url("/api/v1/export").as_str(),
+            "--output-format",
+            "json",
+        ])
+        .env("HOME", home_fixture())
+        .output()
+        .unwrap();
+    // then
+    let envelope: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(envelope["response"]["body"], "/wAB");
+    assert_eq!(envelope["response"]["body_json"], json!(null));
+}
+
+#[test]
+fn http_get_har_appends_exchanges() {
+    // given
+    let mock_server = items_mock_server();
+    let url = mock_server.url("/api/v1/items");
+    let har_file = test_dir("har").join("calls.har");
+    // when
+    for _ in 0..2 {
+        get_cmd()
+            .args(vec!["GET", url.as_str(), "--har"])
+            .arg(&har_file)
+            .env("HOME", home_fixture())
+            .assert()
+            .success();
+    }
+    // then
+    let har: serde_json::Value = serde_json::from_slice(&fs::read(&har_file).unwrap()).unwrap();
+    assert_eq!(har["log"]["version"], "1.2");
+    let entries = har["log"]["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 4);
+    let token_exchange = &entries[0];
+    assert_eq!(token_exchange["request"]["method"], "POST");
+    let token_params = token_exchange["request"]["postData"]["params"].as_array().unwrap();
+    assert!(token_params.contains(&json!({"name": "password", "value": "<redacted>"})));
+    assert!(token_params.contains(&json!({"name": "username", "value": "user"})));
+    let token_response: serde_json::Value = serde_json::from_str(
+        token_exchange["response"]["content"]["text"].as_str().unwrap(),
+    )
+    .unwrap();
+    assert_eq!(token_response["access_token"], "<redacted>");
+    let api_exchange = &entries[1];
+    assert_eq!(api_exchange["request"]["url"], url.as_str());
+    assert!(
+        api_exchange["request"]["headers"]
+            .as_array()
+            .unwrap()
+            .contains(&json!({"name": "authorization", "value": "<redacted>"}))
+    );
+    assert_eq!(api_exchange["response"]["status"], 200);
+    assert_eq!(api_exchange["response"]["content"]["size"], 84);
+    assert!(api_exchange["startedDateTime"].as_str().unwrap().ends_with('Z'));
+}
+
+#[test]
+fn http_get_har_sizes_of_compressed_body() {
+    // given
+    let body = r#"{"items":["compressed","compressed","compressed","compressed"]}"#;
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(body.as_bytes()).unwrap();
+    let gzipped = encoder.finish().unwrap();
+    let mock_server = MockServer::start();
+    mock_server.mock(|when, then| {
+        when.path("/api/v1/get").method(Method::GET);
+        then.status(200)
+            .header("content-encoding", "gzip")
+            .body(&gzipped);
+    });
+    let har_file = test_dir("har-compressed").join("calls.har");
+    // when
+    get_cmd()
+        .args(vec!["GET", mock_server.url("/api/v1/get").as_str(), "--compressed", "--har"])
+        .arg(&har_file)
+        .env("HOME", home_fixture())
+        .assert()
+        .success()
+        .stdout(body);
+    // then
+    let har: serde_json::Value = serde_json::from_slice(&fs::read(&har_file).unwrap()).unwrap();
+    let response = &har["log"]["entries"][1]["response"];
+    assert_eq!(response["content"]["size"], body.len());
+    assert_eq!(response["bodySize"], gzipped.len());
+}
+
+#[test]
+fn http_get_cacert_and_client_certificate() {
+    // given
+    let port = start_tls_server(true);
+    // when/then
+    get_cmd()
+        .args(vec![
+            "GET",
+            &format!("https://localhost:{port}/api/v1/get"),
+            "--cacert",
+            tls_fixture("ca.pem").to_str().unwrap(),
+            "--cert",
+            tls_fixture("client.pem").to_str().unwrap(),
+            "--key",
+            tls_fixture("client.key").to_str().unwrap(),
+            "-v",
+        ])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .success()
+        .stdout("ok")
+        .stderr(predicate::str::contains("* SSL connection using TLSv1.3 / TLS13_"));
+}
+
+#[test]
+fn http_get_capath_and_pkcs12_certificate_from_config() {
+    // given
+    let port = start_tls_server(true);
+    let ca_path = test_dir("capath");
+    fs::copy(tls_fixture("ca.pem"), ca_path.join("ca.pem")).unwrap();
+    let home = home_with_config(
+        "tls-config",
+        &format!(
+            r#"
+- alias: local
+  url: http://localhost:8089/
+  realm: dlobanov
+  clientId: dlobanov_client
+  clientSecret: ''
+  username: user
+  password: password
+  default: true
+  caPath: {}
+  cert: {}:secret
+"#,
+            ca_path.display(),
+            tls_fixture("client.p12").display()
+        ),
+    );
+    // when/then
+    get_cmd()
+        .args(vec!["GET", &format!("https://localhost:{port}/api/v1/get")])
+        .env("HOME", home)
+        .assert()
+        .success()
+        .stdout("ok");
+}
+
+#[test]
+fn http_get_untrusted_server_certificate() {
+    // given
+    let port = start_tls_server(false);
+    // when/then
+    get_cmd()
+        .args(vec!["GET", &format!("https://localhost:{port}/api/v1/get")])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .code(4)
+        .stderr(predicate::str::contains("Failed to connect to https://localhost"));
+}
+
+#[test]
+fn http_get_invalid_cacert() {
+    get_cmd()
+        .args(vec![
+            "GET",
+            "https://localhost:1/api/v1/get",
+            "--cacert",
+            tls_fixture("client.key").to_str().unwrap(),
+        ])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "Failed to build http client for Keycloak 'local'",
+        ));
+}
+
+#[test]
+fn http_get_via_proxy_with_credentials() {
+    // given
+    let proxy = MockServer::start();
+    let mock = proxy.mock(|when, then| {
+        when.path("/api/v1/get")
+            .method(Method::GET)
+            .header("proxy-authorization", "Basic dXNlcjpzZWNyZXQ=");
+        then.status(200).body("ok");
+    });
+    // when/then
+    get_cmd()
+        .args(vec![
+            "GET",
+            "http://backend.invalid/api/v1/get",
+            "-v",
+            "--proxy",
+            &proxy.address().to_string(),
+            "--proxy-user",
+            "user:secret",
+            "--noproxy",
+            "localhost",
+        ])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .success()
+        .stdout("ok")
+        .stderr(predicate::str::contains(format!(
+            "* Host backend.invalid:80 via proxy http://{}/",
+            proxy.address()
+        )))
+        .stderr(predicate::str::contains("secret").not());
+    mock.assert();
+}
+
+#[test]
+fn http_get_via_proxy_from_environment() {
+    // given
+    let proxy = MockServer::start();
+    let mock = proxy.mock(|when, then| {
+        when.path("/api/v1/get").method(Method::GET);
+        then.status(200).body("ok");
+    });
+    // when/then
+    get_cmd()
+        .args(vec!["GET", "http://backend.invalid/api/v1/get"])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .env("HTTP_PROXY", proxy.address().to_string())
+        .env("NO_PROXY", "localhost,127.0.0.1")
+        .assert()
+        .success()
+        .stdout("ok");
+    mock.assert();
+}
+
+#[test]
+fn http_get_verbose_logs_address_used_by_client() {
+    // given
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.path("/api/v1/get").method(Method::GET);
+        then.status(200).body("ok");
+    });
+    // when/then
+    get_cmd()
+        .args(vec![
+            "GET",
+            &format!("http://localhost:{}/api/v1/get", server.port()),
+            "-v",
+        ])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .success()
+        .stdout("ok")
+        .stderr(predicate::str::contains("* Host localhost\n"))
+        .stderr(predicate::str::contains("* IPv4:").not())
+        .stderr(predicate::str::contains(format!(
+            "* Connected to 127.0.0.1 port {}",
+            server.port()
+        )));
+}
+
+#[test]
+fn http_get_with_resolve() {
+    // given
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.path("/api/v1/get").method(Method::GET);
+        then.status(200).body("ok");
+    });
+    // when/then
+    get_cmd()
+        .args(vec![
+            "GET",
+            &format!("http://backend.invalid:{}/api/v1/get", server.port()),
+            "-v",
+            "--resolve",
+            &format!("backend.invalid:{}:127.0.0.1", server.port()),
+        ])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .success()
+        .stdout("ok")
+        .stderr(predicate::str::contains("* IPv4: [127.0.0.1]"))
+        .stderr(predicate::str::contains(format!(
+            "* Connected to 127.0.0.1 port {}",
+            server.port()
+        )));
+    mock.assert();
+}
+
+#[test]
+fn http_get_with_connect_to_keeps_host_header() {
+    // given
+    let server = MockServer::start();
+    let mock = server.mock(|when, then| {
+        when.path("/api/v1/get")
+            .method(Method::GET)
+            .header("host", "backend.invalid");
+        then.status(200).body("ok");
+    });
+    // when/then
+    get_cmd()
+        .args(vec![
+            "GET",
+            "http://backend.invalid/api/v1/get",
+            "-v",
+            "--connect-to",
+            &format!("backend.invalid:80:127.0.0.1:{}", server.port()),
+        ])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .success()
+        .stdout("ok")
+        .stderr(predicate::str::contains(format!(
+            "* Connecting to 127.0.0.1:{} instead",
+            server.port()
+        )))
+        .stderr(predicate::str::contains("> GET http://backend.invalid/api/v1/get"));
+    mock.assert();
+}
+
+#[test]
+#[cfg(unix)]
+fn http_get_over_unix_socket() {
+    // given
+    let socket = test_dir("http_get_over_unix_socket").join("server.sock");
+    let listener = UnixListener::bind(&socket).unwrap();
+    let (sender, received) = mpsc::channel();
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let request = read_request_head(&mut stream);
+        let response = b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\nconnection: close\r\n\r\nok";
+        stream.write_all(response).unwrap();
+        sender.send(String::from_utf8(request).unwrap()).unwrap();
+    });
+    // when/then
+    get_cmd()
+        .args(vec![
+            "GET",
+            "http://backend.invalid/api/v1/get",
+            "q==search",
+            "X-Item:1",
+            "-v",
+            "--unix-socket",
+            socket.to_str().unwrap(),
+        ])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .success()
+        .stdout("ok")
+        .stderr(predicate::str::contains(format!(
+            "* Host backend.invalid:80 via unix socket {}",
+            socket.display()
+        )));
+    let request = received.recv().unwrap().to_lowercase();
+    assert!(request.starts_with("get /api/v1/get?q=search http/1.1\r\n"));
+    assert!(request.contains("\r\nhost: backend.invalid\r\n"));
+    assert!(request.contains("\r\nx-item: 1\r\n"));
+    assert!(request.contains("\r\nauthorization: bearer "));
+}
+
+#[test]
+fn http_get_doesnt_follow_redirect_by_default() {
+    // given
+    let mock_server = MockServer::start();
+    mock_server.mock(|when, then| {
+        when.path("/api/v1/get").method(Method::GET);
+        then.status(302).header("location", "/api/v1/target");
+    });
+    let target = mock_server.mock(|when, then| {
+        when.path("/api/v1/target");
+        then.status(200).body("ok");
+    });
+    // when/then
+    get_cmd()
+        .args(vec!["GET", mock_server.url("/api/v1/get").as_str(), "-i"])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("HTTP/1.1 302 Found"));
+    target.assert_hits(0);
+    get_cmd()
+        .args(vec![
+            "GET",
+            mock_server.url("/api/v1/get").as_str(),
+            "-L",
+            "--max-redirs",
+            "0",
+        ])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("Maximum (0) redirects followed"));
+    target.assert_hits(0);
+}
+
+#[test]
+fn http_post_follows_redirect_without_token_to_other_host() {
+    // given
+    let origin = MockServer::start();
+    let other = MockServer::start();
+    let redirect = origin.mock(|when, then| {
+        when.path("/api/v1/post")
+            .method(Method::POST)
+            .header_exists("authorization");
+        then.status(302).header("location", other.url("/api/v1/target"));
+    });
+    let target = other.mock(|when, then| {
+        when.path("/api/v1/target")
+            .method(Method::GET)
+            .matches(|req| {
+                let headers = req.headers.as_deref().unwrap_or_default();
+                !headers.iter().any(|(name, _)| name == "authorization")
+            });
+        then.status(200).body("ok");
+    });
+    // when/then
+    get_cmd()
+        .args(vec![
+            "POST",
+            origin.url("/api/v1/post").as_str(),
+            "--json-body",
+            "{}",
+            "-L",
+            "-v",
+        ])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .success()
+        .stdout("ok")
+        .stderr(predicate::str::contains(format!(
+            "* Issue another request to this URL: '{}'",
+            other.url("/api/v1/target")
+        )))
+        .stderr(predicate::str::contains("< HTTP/1.1 302 Found"));
+    redirect.assert();
+    target.assert();
+}
+
+#[test]
+fn http_get_follows_redirect_with_token_if_trusted() {
+    // given
+    let origin = MockServer::start();
+    let other = MockServer::start();
+    origin.mock(|when, then| {
+        when.path("/api/v1/get").method(Method::GET);
+        then.status(307).header("location", other.url("/api/v1/target"));
+    });
+    let target = other.mock(|when, then| {
+        when.path("/api/v1/target")
+            .method(Method::GET)
+            .header_exists("authorization");
+        then.status(200).body("ok");
+    });
+    // when/then
+    get_cmd()
+        .args(vec!["GET", origin.url("/api/v1/get").as_str(), "--location-trusted"])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .success()
+        .stdout("ok");
+    target.assert();
+}
+
+#[test]
+fn http_get_follows_redirect_without_user_cookie_to_other_host() {
+    // given
+    let origin = MockServer::start();
+    let other = MockServer::start();
+    let redirect = origin.mock(|when, then| {
+        when.path("/api/v1/get").header("cookie", "session=1");
+        then.status(302).header("location", other.url("/api/v1/target"));
+    });
+    let target = other.mock(|when, then| {
+        when.path("/api/v1/target").matches(|req| {
+            let headers = req.headers.as_deref().unwrap_or_default();
+            !headers.iter().any(|(name, _)| name == "cookie")
+        });
+        then.status(200).body("ok");
+    });
+    // when/then
+    for cookie_args in [["-H", "Cookie: session=1"], ["-b", "session=1"]] {
+        get_cmd()
+            .args(vec!["GET", origin.url("/api/v1/get").as_str(), "-L"])
+            .args(cookie_args)
+            .env("HOME", "tests/cmd/config-list/fs")
+            .assert()
+            .success()
+            .stdout("ok");
+    }
+    redirect.assert_hits(2);
+    target.assert_hits(2);
+}
+
+#[test]
+fn http_get_follows_redirect_without_token_outside_routes() {
+    // given
+    let origin = MockServer::start();
+    let other = MockServer::start();
+    let home = home_with_config(
+        "redirect-outside-routes",
+        &format!(
+            r#"
+- alias: api
+  url: http://localhost:8089/
+  realm: local-realm
+  clientId: dlobanov_client
+  clientSecret: ''
+  username: user
+  password: password
+  default: true
+  routes:
+  - {}
+"#,
+            origin.url("/api/")
+        ),
+    );
+    let api = origin.mock(|when, then| {
+        when.path("/api/v1/get").header_exists("authorization");
+        then.status(302).header("location", "/public/page");
+    });
+    let public = origin.mock(|when, then| {
+        when.path("/public/page").matches(|req| {
+            let headers = req.headers.as_deref().unwrap_or_default();
+            !headers.iter().any(|(name, _)| name == "authorization")
+        });
+        then.status(302).header("location", other.url("/api/v1/target"));
+    });
+    let target = other.mock(|when, then| {
+        when.path("/api/v1/target").matches(|req| {
+            let headers = req.headers.as_deref().unwrap_or_default();
+            !headers.iter().any(|(name, _)| name == "authorization")
+        });
+        then.status(200).body("ok");
+    });
+    // when/then
+    get_cmd()
+        .args(vec!["GET", origin.url("/api/v1/get").as_str(), "--location-trusted"])
+        .env("HOME", &home)
+        .assert()
+        .success()
+        .stdout("ok");
+    api.assert();
+    public.assert();
+    target.assert();
+}
+
+#[test]
+fn http_get_har_records_every_redirect_hop() {
+    // given
+    let mock_server = MockServer::start();
+    mock_server.mock(|when, then| {
+        when.path("/a").method(Method::GET);
+        then.status(302).header("location", "/b");
+    });
+    mock_server.mock(|when, then| {
+        when.path("/b").method(Method::GET);
+        then.status(200).body("ok");
+    });
+    let har_file = test_dir("har-redirects").join("calls.har");
+    // when
+    get_cmd()
+        .args(vec!["GET", mock_server.url("/a").as_str(), "-L", "--har"])
+        .arg(&har_file)
+        .env("HOME", home_fixture())
+        .assert()
+        .success()
+        .stdout("ok");
+    // then
+    let har: serde_json::Value = serde_json::from_slice(&fs::read(&har_file).unwrap()).unwrap();
+    let entries = har["log"]["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0]["request"]["method"], "POST");
+    assert_eq!(entries[1]["request"]["url"], mock_server.url("/a"));
+    assert_eq!(entries[1]["response"]["status"], 302);
+    assert_eq!(entries[1]["response"]["redirectURL"], "/b");
+    assert_eq!(entries[2]["request"]["url"], mock_server.url("/b"));
+    assert_eq!(entries[2]["response"]["status"], 200);
+}
+
+#[test]
+fn http_get_retries_unavailable_after_requested_delay() {
+    // given
+    let mock_server = MockServer::start();
+    let mock = mock_server.mock(|when, then| {
+        when.path("/api/v1/get").method(Method::GET);
+        then.status(503).header("retry-after", "0").body("unavailable");
+    });
+    // when/then
+    get_cmd()
+        .args(vec![
+            "GET",
+            mock_server.url("/api/v1/get").as_str(),
+            "-v",
+            "--retry",
+            "2",
+            "--retry-delay",
+            "1m",
+        ])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .success()
+        .stdout("unavailable")
+        .stderr(predicate::str::contains(
+            "* Problem: HTTP status 503, will retry in 0s, 2 retries left",
+        ));
+    mock.assert_hits(3);
+}
+
+#[test]
+fn http_get_doesnt_wait_for_too_long_retry_after() {
+    // given
+    let mock_server = MockServer::start();
+    let mock = mock_server.mock(|when, then| {
+        when.path("/api/v1/get").method(Method::GET);
+        then.status(503).header("retry-after", "86400").body("unavailable");
+    });
+    // when/then
+    get_cmd()
+        .args(vec!["GET", mock_server.url("/api/v1/get").as_str(), "-v", "--retry", "2"])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .timeout(Duration::from_secs(30))
+        .assert()
+        .success()
+        .stdout("unavailable")
+        .stderr(predicate::str::contains(
+            "* Retry-After of 1day is longer than 10m, giving up",
+        ));
+    mock.assert_hits(1);
+}
+
+#[test]
+fn http_post_is_retried_only_with_all_methods() {
+    // given
+    let mock_server = MockServer::start();
+    let mock = mock_server.mock(|when, then| {
+        when.path("/api/v1/post").method(Method::POST);
+        then.status(502);
+    });
+    let post = |all_methods: bool| {
+        let mut args = vec![
+            "POST",
+            "--retry",
+            "1",
+            "--retry-delay",
+            "10ms",
+            "--retry-status",
+            "502",
+        ];
+        if all_methods {
+            args.push("--retry-all-methods");
+        }
+        get_cmd()
+            .args(args)
+            .arg(mock_server.url("/api/v1/post"))
+            .env("HOME", "tests/cmd/config-list/fs")
+            .assert()
+            .success();
+    };
+    // when/then
+    post(false);
+    mock.assert_hits(1);
+    post(true);
+    mock.assert_hits(3);
+}
+
+#[test]
+fn http_get_read_timeout() {
+    // given
+    let mock_server = MockServer::start();
+    mock_server.mock(|when, then| {
+        when.path("/api/v1/get").method(Method::GET);
+        then.status(200).body("ok").delay(Duration::from_secs(2));
+    });
+    // when/then
+    get_cmd()
+        .args(vec![
+            "GET",
+            mock_server.url("/api/v1/get").as_str(),
+            "--read-timeout",
+            "200ms",
+        ])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .code(5)
+        .stderr(predicate::str::contains("timed out"));
+}
+
+#[test]
+fn http_get_max_time() {
+    // given
+    let mock_server = MockServer::start();
+    mock_server.mock(|when, then| {
+        when.path("/api/v1/get").method(Method::GET);
+        then.status(200).body("ok").delay(Duration::from_secs(2));
+    });
+    // when/then
+    get_cmd()
+        .args(vec!["GET", mock_server.url("/api/v1/get").as_str(), "-m", "0.2"])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .code(5)
+        .stderr(predicate::str::contains("timed out"));
+}
+
+#[test]
+fn http_get_auth_timeout() {
+    // given
+    let keycloak = MockServer::start();
+    keycloak.mock(|when, then| {
+        when.method(Method::POST);
+        then.status(200).delay(Duration::from_secs(2));
+    });
+    let home = home_with_config(
+        "auth-timeout",
+        &format!(
+            r#"
+- alias: slow
+  url: {}
+  realm: dlobanov
+  clientId: dlobanov_client
+  clientSecret: ''
+  username: user
+  password: password
+  default: true
+"#,
+            keycloak.base_url()
+        ),
+    );
+    // when/then
+    get_cmd()
+        .args(vec!["GET", "http://127.0.0.1:1/api/v1/get", "--auth-timeout", "200ms"])
+        .env("HOME", &home)
+        .assert()
+        .code(5)
+        .stderr(predicate::str::contains("Keycloak 'slow' didn't respond in time"));
+}
+
+#[test]
+fn http_get_compressed() {
+    // given
+    let body = r#"{"items":["compressed","compressed","compressed","compressed"]}"#;
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(body.as_bytes()).unwrap();
+    let gzipped = encoder.finish().unwrap();
+    let mock_server = MockServer::start();
+    let mock = mock_server.mock(|when, then| {
+        when.path("/api/v1/get")
+            .method(Method::GET)
+            .header("accept-encoding", "gzip, deflate, br, zstd");
+        then.status(200)
+            .header("content-encoding", "gzip")
+            .body(&gzipped);
+    });
+    // when/then
+    get_cmd()
+        .args(vec![
+            "GET",
+            mock_server.url("/api/v1/get").as_str(),
+            "--compressed",
+            "-v",
+            "-w",
+            "\n%{size_download}",
+        ])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .success()
+        .stdout(format!("{body}\n{}", gzipped.len()))
+        .stderr(predicate::str::contains(format!(
+            "* Decoded gzip: {} bytes received, {} bytes decoded",
+            gzipped.len(),
+            body.len()
+        )));
+    mock.assert();
+}
+
+#[test]
+fn http_get_compressed_raw_to_file() {
+    // given
+    let body: Vec<u8> = (0..=255).collect();
+    let compressed = zstd::encode_all(body.as_slice(), 0).unwrap();
+    let mock_server = MockServer::start();
+    mock_server.mock(|when, then| {
+        when.path("/api/v1/export").method(Method::GET);
+        then.status(200)
+            .header("content-encoding", "zstd")
+            .body(&compressed);
+    });
+    let dir = test_dir("compressed-raw-to-file");
+    let decoded = dir.join("decoded.bin");
+    let raw = dir.join("raw.bin");
+    // when/then
+    get_cmd()
+        .args(vec!["GET", mock_server.url("/api/v1/export").as_str(), "--compressed", "-o"])
+        .arg(&decoded)
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .success();
+    assert_eq!(fs::read(decoded).unwrap(), body);
+    get_cmd()
+        .args(vec![
+            "GET",
+            mock_server.url("/api/v1/export").as_str(),
+            "--compressed",
+            "--raw",
+            "-o",
+        ])
+        .arg(&raw)
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .success();
+    assert_eq!(fs::read(raw).unwrap(), compressed);
+}
+
+#[test]
+fn http_get_cookie_jar_written_and_read_back() {
+    // given
+    let mock_server = MockServer::start();
+    mock_server.mock(|when, then| {
+        when.path("/login").method(Method::GET);
+        then.status(200)
+            .header("set-cookie", "session=abc123; Path=/; HttpOnly")
+            .header("set-cookie", "theme=dark; Path=/ui");
+    });
+    let mock = mock_server.mock(|when, then| {
+        when.path("/api/v1/get")
+            .method(Method::GET)
+            .header("cookie", "session=abc123; lang=en");
+        then.status(200);
+    });
+    let jar = test_dir("cookie-jar").join("cookies.txt");
+    // when
+    get_cmd()
+        .args(vec!["GET", mock_server.url("/login").as_str(), "-c"])
+        .arg(&jar)
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .success();
+    // then
+    #[cfg(unix)]
+    assert_eq!(fs::metadata(&jar).unwrap().permissions().mode() & 0o777, 0o600);
+    let content = fs::read_to_string(&jar).unwrap();
+    assert!(content.starts_with("# Netscape HTTP Cookie File\n"));
+    assert!(content.contains("#HttpOnly_127.0.0.1\tFALSE\t/\tFALSE\t0\tsession\tabc123\n"));
+    assert!(content.contains("127.0.0.1\tFALSE\t/ui\tFALSE\t0\ttheme\tdark\n"));
+    get_cmd()
+        .args(vec!["GET", mock_server.url("/api/v1/get").as_str(), "-v", "-b"])
+        .arg(&jar)
+        .args(vec!["-b", "lang=en"])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("> \"cookie\": \"session=abc123; lang=en\"\n"));
+    mock.assert();
+}
+
+#[test]
+fn http_get_cookie_domain_limited_to_site() {
+    // given
+    let mock_server = MockServer::start();
+    mock_server.mock(|when, then| {
+        when.path("/login").method(Method::GET);
+        then.status(200)
+            .header("set-cookie", "site=1; Domain=example.com")
+            .header("set-cookie", "tld=1; Domain=com")
+            .header("set-cookie", "other=1; Domain=example.org");
+    });
+    let jar = test_dir("cookie-domain").join("cookies.txt");
+    let port = mock_server.port();
+    // when
+    get_cmd()
+        .args(vec!["GET", &format!("http://api.example.com:{port}/login"), "--resolve"])
+        .arg(format!("api.example.com:{port}:127.0.0.1"))
+        .arg("-c")
+        .arg(&jar)
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .success();
+    // then
+    let content = fs::read_to_string(&jar).unwrap();
+    assert!(content.contains(".example.com\tTRUE\t/\tFALSE\t0\tsite\t1\n"));
+    assert!(!content.contains("tld"));
+    assert!(!content.contains("other"));
+}
+
+#[test]
+fn http_get_persists_cookies_per_keycloak_config() {
+    // given
+    let mock_server = MockServer::start();
+    mock_server.mock(|when, then| {
+        when.path("/login").method(Method::GET);
+        then.status(200).header("set-cookie", "session=abc123");
+    });
+    let mock = mock_server.mock(|when, then| {
+        when.path("/api/v1/get")
+            .method(Method::GET)
+            .header("cookie", "session=abc123");
+        then.status(200);
+    });
+    let home = home_with_config(
+        "persist-cookies",
+        r#"
+- alias: local
+  url: http://localhost:8089/
+  realm: dlobanov
+  clientId: dlobanov_client
+  clientSecret: ''
+  username: user
+  password: password
+  default: true
+  persistCookies: true
+"#,
+    );
+    let _ = fs::remove_dir_all(home.join(".curlaut/cookies"));
+    // when
+    for path in ["/login", "/api/v1/get"] {
+        get_cmd()
+            .args(vec!["GET", mock_server.url(path).as_str()])
+            .env("HOME", &home)
+            .assert()
+            .success();
+    }
+    // then
+    mock.assert();
+    let jar = home.join(".curlaut/cookies/local.txt");
+    assert!(fs::read_to_string(&jar).unwrap().contains("\tsession\tabc123\n"));
+    #[cfg(unix)]
+    assert_eq!(fs::metadata(&jar).unwrap().permissions().mode() & 0o777, 0o600);
+}
+
+const SERVER_KEY_PIN: &str = "sha256//NttXSGc1O3CKJIK/3eBCXUtJXd+5eGoTDhYirdxEObY=";
+const CLIENT_KEY_PIN: &str = "sha256//wJ4Yr7gc2VxDMSZiSK+A8NMOps1LlHAffo9Qdp9rSt4=";
+
+#[test]
+fn http_get_pinned_public_key() {
+    // given
+    let port = start_tls_server(false);
+    // when/then
+    get_cmd()
+        .args(vec![
+            "GET",
+            &format!("https://localhost:{port}/api/v1/get"),
+            "--cacert",
+            tls_fixture("ca.pem").to_str().unwrap(),
+            "--pinnedpubkey",
+            &format!("{CLIENT_KEY_PIN};{SERVER_KEY_PIN}"),
+        ])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .success()
+        .stdout("ok");
+}
+
+#[test]
+fn http_get_pinned_public_key_mismatch() {
+    // given
+    let port = start_tls_server(false);
+    // when/then
+    get_cmd()
+        .args(vec![
+            "GET",
+            &format!("https://localhost:{port}/api/v1/get"),
+            "--insecure",
+            "--pinnedpubkey",
+            CLIENT_KEY_PIN,
+        ])
+        .env("HOME", "tests/cmd/config-list/fs")
+        .assert()
+        .code(4)
+        .stderr(predicate::str::contains(format!(
+            "doesn't match pinned keys, observed {SERVER_KEY_PIN}"
+        )));
+}
+
+#[test]
+fn http_get_insecure_still_verifies_keycloak() {
+    // given
+    let port = start_tls_server(false);
+    let home = home_with_config(
+        "insecure-keycloak",
+        &format!(
+            r#"
+- alias: untrusted
+  url: https://localhost:{port}/
+  realm: dlobanov
+  clientId: dlobanov_client
+  clientSecret: ''
+  username: user
+  password: password
+  default: true
+"#
+        ),
+    );
+    // when/then
+    get_cmd()
+        .args(vec!["GET", "http://127.0.0.1:1/api/v1/get", "--insecure"])
+        .env("HOME", &home)
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains("Keycloak 'untrusted' is unreachable"));
+}
+
+/// HTTPS server with certificate of the test CA answering 'ok' on every connection,
+/// requires client certificate issued by the same CA if `client_auth`.
+fn start_tls_server(client_auth: bool) -> u16 {
+    let config = Arc::new(tls_server_config(client_auth));
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let connection = ServerConnection::new(config.clone()).unwrap();
+            let mut stream = StreamOwned::new(connection, stream);
+            read_request_head(&mut stream);
+            let response = b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\nconnection: close\r\n\r\nok";
+            let _ = stream.write_all(response);
+            stream.conn.send_close_notify();
+            let _ = stream.flush();
+        }
+    });
+    port
+}
+
+/// Like `start_tls_server`, but negotiates h2 and answers 'ok' to the first HTTP/2 request.
+fn start_h2_server() -> u16 {
+    let mut config = tls_server_config(false);
+    config.alpn_protocols = vec![b"h2".to_vec()];
+    let config = Arc::new(config);
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let connection = ServerConnection::new(config.clone()).unwrap();
+            let mut stream = StreamOwned::new(connection, stream);
+            let mut preface = [0; 24];
+            if stream.read_exact(&mut preface).is_err() {
+                continue;
+            }
+            // frames are `length(3) type flags stream(4) payload`, the request is a HEADERS one
+            let mut frame = [0; 9];
+            while stream.read_exact(&mut frame).is_ok() {
+                let length = u32::from_be_bytes([0, frame[0], frame[1], frame[2]]) as usize;
+                let mut payload = vec![0; length];
+                if stream.read_exact(&mut payload).is_err() || frame[3] == 1 {
+                    break;
+                }
+            }
+            let settings = [0, 0, 0, 4, 0, 0, 0, 0, 0];
+            let settings_ack = [0, 0, 0, 4, 1, 0, 0, 0, 0];
+            // `:status: 200` is 8th entry of HPACK static table
+            let headers = [0, 0, 1, 1, 4, 0, 0, 0, 1, 0x88];
+            let data = [0, 0, 2, 0, 1, 0, 0, 0, 1, b'o', b'k'];
+            let _ = stream.write_all(&[&settings[..], &settings_ack, &headers, &data].concat());
+            let _ = stream.flush();
+            // the client closes the connection when it's done
+            let _ = stream.read_to_end(&mut Vec::new());
+        }
+    });
+    port
+}
+
+fn tls_server_config(client_auth: bool) -> ServerConfig {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let chain = CertificateDer::pem_file_iter(tls_fixture("server.pem"))
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let key = PrivateKeyDer::from_pem_file(tls_fixture("server.key")).unwrap();
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .unwrap();
+    let builder = if client_auth {
+        let mut roots = RootCertStore::empty();
+        roots
+            .add(CertificateDer::from_pem_file(tls_fixture("ca.pem")).unwrap())
+            .unwrap();
+        let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+            .build()
+            .unwrap();
+        builder.with_client_cert_verifier(verifier)
+    } else {
+        builder.with_no_client_auth()
+    };
+    builder.with_single_cert(chain, key).unwrap()
+}
+
+/// Reads until the end of headers, the body is ignored.
+fn read_request_head(stream: &mut impl Read) -> Vec<u8> {
+    let mut request = Vec::new();
+    let mut buffer = [0; 1024];
+    while !request.windows(4).any(|end| end == b"\r\n\r\n") {
+        match stream.read(&mut buffer) {
+            Ok(0) | Err(_) => break,
+            Ok(read) => request.extend_from_slice(&buffer[..read]),
+        }
+    }
+    request
+}
+
+fn tls_fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/tls").join(name)
+}
+
+fn test_dir(test_name: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(test_name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn home_fixture() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/cmd/config-list/fs")
+}
+
+fn home_with_config(test_name: &str, config: &str) -> PathBuf {
+    let home = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(test_name);
+    fs::create_dir_all(home.join(".curlaut")).unwrap();
+    fs::write(home.join(".curlaut/auth.yaml"), config).unwrap();
+    home
+}
+
 fn get_cmd() -> Command {
     Command::cargo_bin("curlaut").unwrap()
 }